@@ -1,13 +1,52 @@
 #let p = plugin("./linalg.wasm")
 
-#let mat_bytes(m) = bytes(m.rows.map(row => row.map(item => item.text.replace("−","-")).join(",")).join(";"))
-#let bytes_mat(b) = math.mat(..str(b).split(";").map(row_s => row_s.split(",").map(entry_s => float(entry_s))))
+// Entries are sent as written; the plugin reads fractions, complex numbers and Typst's minus sign.
+// Entries of matrices returned by this package send the full precision value they carry instead.
+#let entry_text(item) = if item.has("children") and item.children.len() > 0 and item.children.first().func() == metadata { item.children.first().value } else { item.text }
+#let mat_bytes(m) = bytes(m.rows.map(row => row.map(entry_text).join(",")).join(";"))
+// The plugin writes results in full precision so that they can be passed on. Every entry keeps
+// that value as metadata and shows it as in `shown`, by default the plugin's default format,
+// with a proper minus sign.
+#let bytes_mat(b, shown: none) = {
+  let shown = if shown == none { p.format_matrix(b, bytes("")) } else { shown }
+  let cells(s) = str(s).split(";").map(row_s => row_s.split(","))
+  math.mat(..cells(b).zip(cells(shown)).map(((row, shown_row)) => row.zip(shown_row).map(((entry_s, shown_s)) => [#metadata(entry_s)#shown_s.replace("-","−")])))
+}
 
-#let vec_bytes(v) = bytes(v.map(item => str(item).replace("−","-")).join(","))
+#let vec_bytes(v) = bytes(v.map(item => str(item)).join(","))
+#let mats_bytes(ms) = bytes(ms.map(m => str(mat_bytes(m))).join("|"))
+#let bytes_mats(b) = str(b).split("|").map(mat_s => bytes_mat(bytes(mat_s)))
 #let bytes_vec(b) = str(b).split(",").map(entry_s => float(entry_s))
 
-#let num_bytes(n) = bytes(str(n).replace("−","-"))
+#let num_bytes(n) = bytes(str(n))
 #let bytes_num(b) = float(str(b))
+
+// Sparse matrices are dictionaries `(rows: m, cols: n, entries: ((i, j, value), ...))`.
+#let sparse_bytes(s) = bytes(
+  (str(s.rows) + "," + str(s.cols), ..s.entries.map(((i, j, v)) => str(i) + "," + str(j) + "," + str(v))).join(";")
+)
+#let bytes_sparse(b) = {
+  let (shape, ..entries) = str(b).split(";")
+  let (rows, cols) = shape.split(",").map(int)
+  (rows: rows, cols: cols, entries: entries.map(e => {
+    let (i, j, v) = e.split(",")
+    (int(i), int(j), float(v))
+  }))
+}
+
+// Point lists are arrays of coordinate arrays, e.g. `((0, 1, 2), (3, 4, 5))`.
+#let points_bytes(pts) = bytes(pts.map(pt => pt.map(x => str(x)).join(",")).join(";"))
+#let bytes_points(b) = str(b).split(";").map(row_s => row_s.split(",").map(entry_s => float(entry_s)))
+
+// Angles may be given either as Typst angles or as floats in radians.
+#let rad(a) = if type(a) == angle { a.rad() } else { a }
+
+// Tolerances below which floating point values count as zero: `tol` is absolute,
+// `rel` is relative to the norm of the matrix. Without either, a small multiple of
+// machine epsilon times the norm is used.
+#let tol_bytes(tol, rel) = if tol != none { bytes("abs:" + str(tol)) } else if rel != none { bytes("rel:" + str(rel)) } else { bytes("") }
+
+#let range_bytes(r) = bytes(str(r.at(0)) + ".." + str(r.at(1)))
   
 #let add(m1, m2) = bytes_mat(p.add(mat_bytes(m1), mat_bytes(m2)))
 #let sub(m1, m2) = bytes_mat(p.sub(mat_bytes(m1), mat_bytes(m2)))
@@ -15,12 +54,130 @@
 #let mul_vec(m, v) = bytes_vec(p.mul_vec(mat_bytes(m), vec_bytes(v)))
 
 #let rowswap(m, r1, r2) = bytes_mat(p.rowswap(mat_bytes(m), num_bytes(r1), num_bytes(r2)))
+// Returns `(x, y, z, w, visible)` for every point; see `Matrix::transform_points`. With a
+// `viewport: (width, height)`, `x` and `y` are page coordinates: `y` grows downwards.
+#let transform_points(m, pts, near: 1e-6, viewport: none) = {
+  let viewport_bytes = if viewport == none { bytes("") } else { vec_bytes(viewport) }
+  bytes_points(p.transform_points(mat_bytes(m), points_bytes(pts), num_bytes(near), viewport_bytes))
+    .map(((x, y, z, w, visible)) => (x, y, z, w, visible == 1))
+}
+// Rewrites the entries of a matrix, or an array of numbers, with `digits` significant
+// digits (`none` for as many as needed) or a fixed number of `decimals`. Numbers within
+// `snap` of a nonzero integer, relative to their size, become that integer, rounding noise
+// becomes 0, and numbers outside `sci` are written in scientific notation. Arrays come back
+// as strings. Matrices returned by the other functions are shown with the defaults, and
+// only the shown entries are rounded: the matrix passes on its full precision values.
+#let format(m, digits: 12, decimals: none, snap: 1e-12, sci: (1e-5, 1e15)) = {
+  let digits_opt = if decimals != none { "dec:" + str(decimals) } else if digits == none { "sig:0" } else { "sig:" + str(digits) }
+  let opts = bytes(digits_opt + ",snap:" + str(snap) + ",sci:" + str(sci.at(0)) + ".." + str(sci.at(1)))
+  if type(m) == array { str(p.format_vector(vec_bytes(m), opts)).split(",") } else {
+    let m_bytes = mat_bytes(m)
+    bytes_mat(m_bytes, shown: p.format_matrix(m_bytes, opts))
+  }
+}
+// Writes every entry exactly where possible: as a fraction with denominator at most `den`,
+// or as a fraction times the square root of a squarefree number up to `surd`, within `tol`.
+// Like `format`, the matrix passes on its full precision values.
+#let exact(m, den: 1000, tol: 1e-9, surd: 10) = {
+  let m_bytes = mat_bytes(m)
+  bytes_mat(m_bytes, shown: p.exact(m_bytes, bytes("den:" + str(den) + ",tol:" + str(tol) + ",surd:" + str(surd))))
+}
+// Renders a matrix as Typst math or LaTeX (`markup: "latex"`), with a line before column
+// `augment` and boxed `pivots` if asked. The matrix may also be an array of rows, whose
+// entries can be strings such as "1/2" or "1+2i". Use `typeset` to show the Typst result.
+#let render(m, markup: "typst", augment: none, pivots: false) = {
+  let m_bytes = if type(m) == array { points_bytes(m) } else { mat_bytes(m) }
+  let opts = (markup,) + if augment != none { ("augment:" + str(augment),) } + if pivots { ("pivots",) }
+  str(p.render(m_bytes, bytes(opts.join(","))))
+}
+#let typeset(m, augment: none, pivots: false) = eval(render(m, augment: augment, pivots: pivots), mode: "math")
+#let slice(m, rows, cols) = bytes_mat(p.slice(mat_bytes(m), range_bytes(rows), range_bytes(cols)))
+// Orthonormalises the given vectors in order; `method` is "classical", "modified" or "reorthogonalised".
+// Vectors with entries written as fractions, such as "1/2", are orthogonalised exactly without
+// normalising, and complex entries such as "1+2i" are accepted; such results come back as strings.
+#let gram_schmidt(..vectors, method: "modified") = str(p.gram_schmidt(points_bytes(vectors.pos()), bytes(method)))
+  .split(";").map(row_s => row_s.split(",").map(entry_s => if entry_s.match(regex("^[-+0-9.eE]+$")) != none { float(entry_s) } else { entry_s }))
+// Exact elimination steps as a dictionary `(start: m, steps: (..))`. Each step has
+// `op` ("swap", "scale" or "add"), a readable `text` such as "R2 ← R2 − 3·R1", and
+// the `matrix` after it; entries are strings such as "-3/4", and rows count from 0.
+#let elimination_steps(m, reduced: true) = json(p.elimination_steps(mat_bytes(m), bytes(repr(reduced))))
+// Exact cofactor expansion as a tree `(matrix: m, value: "3", line: (row: 0), terms: (..))`,
+// where each term has `row`, `col`, `entry`, `sign` and the expanded `minor`. Expands along
+// the given `row` or `col`, or else the line with the most zeros. Matrices up to 8×8 are accepted.
+#let det_laplace(m, row: none, col: none) = {
+  let line = if row != none { "row," + str(row) } else if col != none { "col," + str(col) } else { "" }
+  json(p.det_laplace(mat_bytes(m), bytes(line)))
+}
 
-#let REF(m) = bytes_mat(p.REF(mat_bytes(m)))
-#let RREF(m) = bytes_mat(p.RREF(mat_bytes(m)))
-#let inverse(m) = bytes_mat(p.inverse(mat_bytes(m)))
+#let REF(m, tol: none, rel: none) = bytes_mat(p.echelon(mat_bytes(m), tol_bytes(tol, rel)))
+#let RREF(m, tol: none, rel: none) = bytes_mat(p.reduced_echelon(mat_bytes(m), tol_bytes(tol, rel)))
+#let inverse(m, tol: none, rel: none) = bytes_mat(p.inverse(mat_bytes(m), tol_bytes(tol, rel)))
+#let rank(m, tol: none, rel: none) = int(str(p.rank(mat_bytes(m), tol_bytes(tol, rel))))
+
+#let is_symmetric(m, tol: none, rel: none) = str(p.is_symmetric(mat_bytes(m), tol_bytes(tol, rel))) == "true"
+#let is_skew_symmetric(m, tol: none, rel: none) = str(p.is_skew_symmetric(mat_bytes(m), tol_bytes(tol, rel))) == "true"
+#let is_diagonal(m, tol: none, rel: none) = str(p.is_diagonal(mat_bytes(m), tol_bytes(tol, rel))) == "true"
+#let is_lower_triangular(m, tol: none, rel: none) = str(p.is_lower_triangular(mat_bytes(m), tol_bytes(tol, rel))) == "true"
+#let is_upper_triangular(m, tol: none, rel: none) = str(p.is_upper_triangular(mat_bytes(m), tol_bytes(tol, rel))) == "true"
+#let is_invertible(m, tol: none, rel: none) = str(p.is_invertible(mat_bytes(m), tol_bytes(tol, rel))) == "true"
 #let exp(m) = bytes_mat(p.exp(mat_bytes(m)))
 #let pow(m, i) = bytes_mat(p.pow(mat_bytes(m), num_bytes(i)))
 
 #let det(m) = bytes_num(p.det(mat_bytes(m)))
 #let trace(m) = bytes_num(p.trace(mat_bytes(m)))
+
+#let sparse(m) = bytes_sparse(p.dense_to_sparse(mat_bytes(m)))
+#let dense(s) = bytes_mat(p.sparse_to_dense(sparse_bytes(s)))
+#let sparse_transpose(s) = bytes_sparse(p.sparse_transpose(sparse_bytes(s)))
+#let sparse_mul(s1, s2) = bytes_sparse(p.sparse_mul(sparse_bytes(s1), sparse_bytes(s2)))
+#let sparse_mul_dense(s, m) = bytes_mat(p.sparse_mul_dense(sparse_bytes(s), mat_bytes(m)))
+#let sparse_mul_vec(s, v) = bytes_vec(p.sparse_mul_vec(sparse_bytes(s), vec_bytes(v)))
+#let sparse_solve(s, b, tol: 1e-10) = bytes_vec(p.sparse_solve(sparse_bytes(s), vec_bytes(b), num_bytes(tol)))
+
+#let homogeneous(m) = bytes_mat(p.homogeneous(mat_bytes(m)))
+#let translation(..offset) = bytes_mat(p.translation(vec_bytes(offset.pos())))
+#let scaling(..factors) = bytes_mat(p.scaling(vec_bytes(factors.pos())))
+#let shear(xy: 0, xz: 0, yx: 0, yz: 0, zx: 0, zy: 0) = bytes_mat(p.shear_3d(..(xy, xz, yx, yz, zx, zy).map(num_bytes)))
+#let rotation_2d(a) = bytes_mat(p.rotation_2d(num_bytes(rad(a))))
+#let rotation_x(a) = bytes_mat(p.rotation_x_3d(num_bytes(rad(a))))
+#let rotation_y(a) = bytes_mat(p.rotation_y_3d(num_bytes(rad(a))))
+#let rotation_z(a) = bytes_mat(p.rotation_z_3d(num_bytes(rad(a))))
+#let perspective(fov, aspect, near, far) = bytes_mat(p.perspective(..(rad(fov), aspect, near, far).map(num_bytes)))
+#let frustum(left, right, bottom, top, near, far) = bytes_mat(p.frustum(..(left, right, bottom, top, near, far).map(num_bytes)))
+#let orthographic(left, right, bottom, top, near, far) = bytes_mat(p.orthographic(..(left, right, bottom, top, near, far).map(num_bytes)))
+#let look_at(eye, target, up: (0, 1, 0)) = bytes_mat(p.look_at(vec_bytes(eye), vec_bytes(target), vec_bytes(up)))
+#let rotation_axis(axis, a) = bytes_mat(p.rotation_axis_angle(vec_bytes(axis), num_bytes(rad(a))))
+#let axis_angle(m) = {
+  let res = bytes_vec(p.axis_angle(mat_bytes(m)))
+  (axis: res.slice(0, 3), angle: res.at(3) * 1rad)
+}
+#let euler_to_matrix(angles, convention: "xyz") = bytes_mat(p.euler_to_matrix(vec_bytes(angles.map(rad)), bytes(convention)))
+#let matrix_to_euler(m, convention: "xyz") = bytes_vec(p.matrix_to_euler(mat_bytes(m), bytes(convention))).map(a => a * 1rad)
+#let quaternion_to_matrix(q) = bytes_mat(p.quaternion_to_matrix(vec_bytes(q)))
+#let matrix_to_quaternion(m) = bytes_vec(p.matrix_to_quaternion(mat_bytes(m)))
+// `frames` rotation matrices turning evenly from `start` to `end`, for animating across pages.
+#let slerp_frames(start, end, frames) = bytes_mats(p.slerp_frames(mat_bytes(start), mat_bytes(end), num_bytes(frames)))
+
+// 2D transforms are 3×3 homogeneous matrices; `translation(dx, dy)` and
+// `scaling(sx, sy)` above also produce them.
+#let rotation_about(a, center: (0, 0)) = bytes_mat(p.rotation_2d_about(..(rad(a), ..center).map(num_bytes)))
+#let scaling_about(sx, sy, center: (0, 0)) = bytes_mat(p.scaling_2d_about(..(sx, sy, ..center).map(num_bytes)))
+#let reflection(a, through: (0, 0)) = bytes_mat(p.reflection_2d(..(rad(a), ..through).map(num_bytes)))
+#let shear_2d(kx: 0, ky: 0) = bytes_mat(p.shear_2d(num_bytes(kx), num_bytes(ky)))
+// Applies the transforms from first to last.
+#let compose(..ms) = bytes_mat(p.compose(mats_bytes(ms.pos())))
+#let transform_points_2d(m, pts) = bytes_points(p.transform_points_2d(mat_bytes(m), points_bytes(pts)))
+// Both return `(transform: m, residuals: (..))`, with the reprojection error of every pair.
+#let bytes_estimate(b) = {
+  let (m, residuals) = str(b).split("|")
+  (transform: bytes_mat(bytes(m)), residuals: bytes_vec(bytes(residuals)))
+}
+#let estimate_affine(source, target) = bytes_estimate(p.estimate_affine(points_bytes(source), points_bytes(target)))
+#let estimate_homography(source, target) = bytes_estimate(p.estimate_homography(points_bytes(source), points_bytes(target)))
+// Aligns `source` onto `target`, which are lists of points of equal length. Returns
+// `(rotation: m, translation: v, scale: s, rmsd: e)` with `target ≈ s * m * source + v`.
+#let kabsch(source, target, scale: false) = {
+  let (rotation, translation, fit) = str(p.kabsch(points_bytes(source), points_bytes(target), bytes(repr(scale)))).split("|")
+  let (s, rmsd) = bytes_vec(bytes(fit))
+  (rotation: bytes_mat(bytes(rotation)), translation: bytes_vec(bytes(translation)), scale: s, rmsd: rmsd)
+}
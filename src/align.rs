@@ -0,0 +1,106 @@
+use crate::error::LinalgError;
+use crate::matrix::Matrix;
+use crate::vector::Vector;
+
+/// The similarity `x ↦ scale * rotation * x + translation` that best maps one
+/// point set onto another, and the root mean square deviation that remains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment {
+    pub rotation: Matrix<f64>,
+    pub translation: Vector<f64>,
+    pub scale: f64,
+    pub rmsd: f64,
+}
+
+fn centroid(points: &Matrix<f64>) -> Vector<f64> {
+    let mut sum = Vector::zero(points.ncols());
+    for point in points.rows() {
+        for (s, x) in sum.entries.iter_mut().zip(point) {
+            *s += x;
+        }
+    }
+    sum.scale(1.0 / points.nrows() as f64)
+}
+
+fn centered(points: &Matrix<f64>, center: &Vector<f64>) -> Matrix<f64> {
+    let mut out = points.clone();
+    for i in 0..out.nrows() {
+        for (x, c) in out[i].iter_mut().zip(&center.entries) {
+            *x -= c;
+        }
+    }
+    out
+}
+
+impl Matrix<f64> {
+    /// Aligns the rows of `source` to the matching rows of `target` with the
+    /// Kabsch algorithm, minimising the sum of squared distances.
+    ///
+    /// The rotation is proper: if the best orthogonal map would be a
+    /// reflection, the closest rotation is returned instead. With
+    /// `with_scale`, a uniform scale is fitted as well (Umeyama's extension);
+    /// otherwise it is 1.
+    pub fn kabsch(
+        source: &Matrix<f64>,
+        target: &Matrix<f64>,
+        with_scale: bool,
+    ) -> Result<Alignment, LinalgError> {
+        if source.nrows() != target.nrows() || source.ncols() != target.ncols() {
+            return Err(LinalgError::mismatch(
+                "align point sets of",
+                source.shape(),
+                target.shape(),
+            ));
+        }
+        if source.nrows() == 0 {
+            return Err(LinalgError::invalid("Point sets cannot be empty"));
+        }
+        let (source_center, target_center) = (centroid(source), centroid(target));
+        let p = centered(source, &source_center);
+        let q = centered(target, &target_center);
+
+        let covariance = &p.transpose() * &q;
+        let (u, sigma, v) = covariance.svd()?;
+        // Flipping the direction of the weakest singular pair turns a
+        // reflection into the nearest rotation.
+        let dim = source.ncols();
+        let mut flip = Matrix::id(dim);
+        if (&v * &u.transpose()).det()? < 0.0 {
+            flip[dim - 1][dim - 1] = -1.0;
+        }
+        let rotation = &(&v * &flip) * &u.transpose();
+
+        let scale = if with_scale {
+            let spread: f64 = p.as_slice().iter().map(|x| x * x).sum();
+            if spread == 0.0 {
+                return Err(LinalgError::invalid(
+                    "Source points must not all coincide to fit a scale",
+                ));
+            }
+            let explained: f64 = (0..dim).map(|i| sigma[i] * flip[i][i]).sum();
+            explained / spread
+        } else {
+            1.0
+        };
+
+        let rotated_center = rotation.mul_vector(&source_center)?;
+        let translation = &target_center - &rotated_center.scale(scale);
+
+        let mut squared_error = 0.0;
+        for (point, goal) in source.rows().zip(target.rows()) {
+            let image = rotation.mul_vector(&Vector::from(point.to_vec()))?;
+            for k in 0..dim {
+                let diff = scale * image[k] + translation[k] - goal[k];
+                squared_error += diff * diff;
+            }
+        }
+        let rmsd = (squared_error / source.nrows() as f64).sqrt();
+
+        Ok(Alignment {
+            rotation,
+            translation,
+            scale,
+            rmsd,
+        })
+    }
+}
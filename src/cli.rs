@@ -0,0 +1,443 @@
+use std::io::Read;
+use std::ops::Range;
+
+use fraction::Fraction;
+
+use crate::common::*;
+use crate::convert::Convertable;
+use crate::error::LinalgError;
+use crate::format::{format_entries, FormatScalar, NumberFormat};
+use crate::laplace::Line;
+use crate::literal::parse_scalar;
+use crate::markup::{Markup, MarkupScalar, RenderOptions};
+use crate::matrix::{Echelon, Matrix};
+use crate::orthogonal::{gram_schmidt, GramSchmidt};
+use crate::rational::Recovery;
+use crate::tolerance::{frobenius_norm, Tolerance};
+use crate::trace::steps_to_json;
+use crate::vector::Vector;
+
+pub const USAGE: &str = "\
+Usage: linalg-bin <operation> <input>... [options]
+       linalg-bin repl
+       linalg-bin bench
+
+Inputs are files holding a matrix in the plugin's text format, with rows
+separated by ';' or newlines and entries by ','. '-' reads standard input.
+Vectors may be written as a single row or column.
+
+Operations:
+  transpose A, neg A, add A B, sub A B, mul A B, mul_vec A v, pow A k,
+  echelon A, reduced_echelon A, rank A, det A, trace A, inverse A,
+  solve A b, is_symmetric A, is_skew_symmetric A, is_diagonal A,
+  is_lower_triangular A, is_upper_triangular A, is_invertible A,
+  elimination_steps A, det_laplace A [row,i | col,j], slice A i..j k..l,
+  gram_schmidt A [classical | modified | reorthogonalised]
+  Floating point only: exp A, least_squares A b, qr A, svd A,
+  homogeneous A, exact A, kabsch P Q [scale], estimate_affine P Q,
+  estimate_homography P Q
+  The plugin's render and format are the --output and --format options.
+  Rotations, projections and transforms, transform_points, slerp_frames
+  and sparse matrices are only available in the plugin.
+
+Options:
+  --exact           compute with fractions instead of floating point
+  --output FORM     text (default), typst or latex
+  --format OPTS     number format for text output, such as sig:6 or dec:2
+  --tol TOL         zero tolerance, abs:x or rel:x
+  --recover OPTS    limits for 'exact', such as den:100,surd:5
+  --augment N       draw a line before column N in typst and latex output
+  --pivots          box the pivots in typst and latex output";
+
+/// What the command line asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    pub operation: String,
+    pub inputs: Vec<String>,
+    pub exact: bool,
+    /// `None` for the plugin's text format.
+    pub markup: Option<Markup>,
+    pub format: NumberFormat,
+    pub tol: Tolerance,
+    pub recovery: Recovery,
+    pub render: RenderOptions,
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Self, LinalgError> {
+        let mut args = args.iter();
+        let operation = args
+            .next()
+            .ok_or_else(|| LinalgError::invalid("No operation given"))?
+            .clone();
+        let mut command = Command {
+            operation,
+            inputs: vec![],
+            exact: false,
+            markup: None,
+            format: NumberFormat::default(),
+            tol: Tolerance::default(),
+            recovery: Recovery::default(),
+            render: RenderOptions::default(),
+        };
+        while let Some(arg) = args.next() {
+            let mut value = |flag: &str| {
+                args.next().ok_or_else(|| {
+                    LinalgError::InvalidArgument(format!("Option {} needs a value", flag))
+                })
+            };
+            match arg.as_str() {
+                "--exact" => command.exact = true,
+                "--pivots" => command.render.pivots = true,
+                "--output" => {
+                    command.markup = match value(arg)?.as_str() {
+                        "text" => None,
+                        "typst" => Some(Markup::Typst),
+                        "latex" => Some(Markup::Latex),
+                        other => {
+                            return Err(LinalgError::InvalidArgument(format!(
+                                "Unknown output form '{}'",
+                                other
+                            )))
+                        }
+                    }
+                }
+                "--format" => command.format = value(arg)?.parse()?,
+                "--tol" => command.tol = value(arg)?.parse()?,
+                "--recover" => command.recovery = value(arg)?.parse()?,
+                "--augment" => {
+                    let col = value(arg)?;
+                    command.render.augment = Some(col.parse().map_err(|_| {
+                        LinalgError::InvalidArgument(format!("Invalid column '{}'", col))
+                    })?);
+                }
+                flag if flag.starts_with("--") => {
+                    return Err(LinalgError::InvalidArgument(format!(
+                        "Unknown option '{}'",
+                        flag
+                    )))
+                }
+                _ => command.inputs.push(arg.clone()),
+            }
+        }
+        Ok(command)
+    }
+
+    /// Runs the command and returns what should be printed.
+    pub fn run(&self) -> Result<String, LinalgError> {
+        // Arguments such as the exponent of `pow` or the ranges of `slice`
+        // are given inline, after the matrices.
+        let (matrices, arguments) = match self.operation.as_str() {
+            "pow" | "det_laplace" | "gram_schmidt" => (1, 1),
+            "slice" => (1, 2),
+            "kabsch" => (2, 1),
+            _ => (0, 0),
+        };
+        let inline = self.inputs.len().saturating_sub(matrices).min(arguments);
+        let (files, arguments) = self
+            .inputs
+            .split_at(self.inputs.len().saturating_sub(inline));
+        let mut texts = Vec::with_capacity(files.len());
+        let mut stdin_read = false;
+        for input in files {
+            texts.push(read_input(input, &mut stdin_read)?);
+        }
+        if !self.exact {
+            return self.render(&run_float(self, &texts, arguments)?);
+        }
+        if FLOAT_OPERATIONS.contains(&self.operation.as_str()) {
+            return Err(LinalgError::InvalidArgument(format!(
+                "'{}' is not available with --exact",
+                self.operation
+            )));
+        }
+        self.render(&run_generic::<Fraction>(self, &texts, arguments)?)
+    }
+
+    fn render<T: Scalar>(&self, value: &Value<T>) -> Result<String, LinalgError> {
+        Ok(match (value, self.markup) {
+            (Value::Matrix(mat), None) => matrix_to_text(mat, &self.format),
+            (Value::Matrix(mat), Some(markup)) => mat.to_markup(&RenderOptions {
+                markup,
+                ..self.render
+            })?,
+            (Value::Vector(v), None) => format_entries(&v.entries, &self.format).join(","),
+            (Value::Vector(v), Some(_)) => self.render(&Value::Matrix(v.column_matrix()))?,
+            (Value::Scalar(x), None) => x.format_with(&self.format),
+            (Value::Scalar(x), Some(markup)) => x.to_markup(markup),
+            (Value::List(values), _) => values
+                .iter()
+                .map(|value| self.render(value))
+                .collect::<Result<Vec<String>, LinalgError>>()?
+                .join("\n\n"),
+            (Value::Text(text), _) => text.clone(),
+        })
+    }
+}
+
+/// Operations that need floating point arithmetic.
+const FLOAT_OPERATIONS: [&str; 9] = [
+    "exp",
+    "least_squares",
+    "qr",
+    "svd",
+    "homogeneous",
+    "exact",
+    "kabsch",
+    "estimate_affine",
+    "estimate_homography",
+];
+
+/// The result of an operation.
+enum Value<T: Scalar> {
+    Scalar(T),
+    Vector(Vector<T>),
+    Matrix(Matrix<T>),
+    List(Vec<Value<T>>),
+    /// Printed as it is, such as booleans and JSON.
+    Text(String),
+}
+
+fn read_input(input: &str, stdin_read: &mut bool) -> Result<String, LinalgError> {
+    let cannot_read = |err: std::io::Error| {
+        LinalgError::InvalidArgument(format!("Cannot read '{}': {}", input, err))
+    };
+    if input != "-" {
+        return std::fs::read_to_string(input).map_err(cannot_read);
+    }
+    if *stdin_read {
+        return Err(LinalgError::invalid("Standard input can only be read once"));
+    }
+    *stdin_read = true;
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .map_err(cannot_read)?;
+    Ok(text)
+}
+
+/// Writes one row per line, with entries separated by `,`.
+pub fn matrix_to_text<T: Scalar>(mat: &Matrix<T>, format: &NumberFormat) -> String {
+    let scale = frobenius_norm(mat.as_slice());
+    mat.rows()
+        .map(|row| {
+            row.iter()
+                .map(|x| x.format_scaled(format, scale))
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Reads the plugin's text format, also accepting newlines between rows.
+pub fn parse_matrix<T: Scalar>(text: &str) -> Result<Matrix<T>, LinalgError> {
+    let rows: Vec<&str> = text
+        .lines()
+        .flat_map(|line| line.split(';'))
+        .map(str::trim)
+        .filter(|row| !row.is_empty())
+        .collect();
+    if rows.is_empty() {
+        return Err(LinalgError::parse("Empty matrix".to_owned(), None));
+    }
+    let mut entries = Vec::with_capacity(rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        let mut parsed = vec![];
+        for (j, entry) in row.split(',').enumerate() {
+            parsed.push(
+                parse_scalar(entry).map_err(|err| err.at(crate::error::Position::Cell(i, j)))?,
+            );
+        }
+        entries.push(parsed);
+    }
+    Matrix::from_rows(entries)
+}
+
+/// Reads a single row or column as a vector.
+fn parse_vector<T: Scalar>(text: &str) -> Result<Vector<T>, LinalgError> {
+    matrix_to_vector(parse_matrix(text)?)
+}
+
+/// Takes the entries of a single row or column.
+pub fn matrix_to_vector<T: Scalar>(mat: Matrix<T>) -> Result<Vector<T>, LinalgError> {
+    if mat.nrows() != 1 && mat.ncols() != 1 {
+        return Err(LinalgError::InvalidArgument(format!(
+            "Expected a vector, found a {}×{} matrix",
+            mat.nrows(),
+            mat.ncols()
+        )));
+    }
+    Ok(Vector::from(mat.as_slice().to_vec()))
+}
+
+fn check_arity(command: &Command, texts: &[String], arity: usize) -> Result<(), LinalgError> {
+    if texts.len() != arity {
+        return Err(LinalgError::InvalidArgument(format!(
+            "'{}' takes {} input{}, but {} {} given",
+            command.operation,
+            arity,
+            if arity == 1 { "" } else { "s" },
+            texts.len(),
+            if texts.len() == 1 { "was" } else { "were" }
+        )));
+    }
+    Ok(())
+}
+
+/// Operations that work over any scalar, so also exactly.
+fn run_generic<T: Scalar>(
+    command: &Command,
+    files: &[String],
+    arguments: &[String],
+) -> Result<Value<T>, LinalgError> {
+    let op = command.operation.as_str();
+    let tol = command.tol;
+    let arity = match op {
+        "add" | "sub" | "mul" | "mul_vec" | "solve" => 2,
+        "pow" if arguments.is_empty() => {
+            return Err(LinalgError::invalid("'pow' takes a matrix and an exponent"))
+        }
+        "slice" if arguments.len() != 2 => {
+            return Err(LinalgError::invalid(
+                "'slice' takes a matrix, a row range and a column range",
+            ))
+        }
+        _ => 1,
+    };
+    check_arity(command, files, arity)?;
+    let a = parse_matrix::<T>(&files[0])?;
+    let flag = |b: bool| Value::Text(b.to_string());
+
+    Ok(match op {
+        "transpose" => Value::Matrix(a.transpose()),
+        "neg" => Value::Matrix(-a),
+        "add" => Value::Matrix(a.checked_add(&parse_matrix(&files[1])?)?),
+        "sub" => Value::Matrix(a.checked_sub(&parse_matrix(&files[1])?)?),
+        "mul" => Value::Matrix(a.checked_mul(&parse_matrix(&files[1])?)?),
+        "mul_vec" => Value::Vector(a.mul_vector(&parse_vector(&files[1])?)?),
+        "pow" => {
+            let power = arguments[0].trim().parse::<i64>().map_err(|_| {
+                LinalgError::InvalidArgument(format!("Invalid exponent '{}'", arguments[0]))
+            })?;
+            Value::Matrix(a.powi(power)?)
+        }
+        "echelon" => Value::Matrix(a.echelon_within(tol).0),
+        "reduced_echelon" => Value::Matrix(a.reduced_echelon_within(tol)),
+        "rank" => Value::Text(a.rank_within(tol).to_string()),
+        "det" => Value::Scalar(a.det()?),
+        "trace" => Value::Scalar(a.trace()?),
+        "inverse" => Value::Matrix(a.inverse_within(tol)?),
+        "solve" => Value::Vector(a.solve(&parse_vector(&files[1])?)?),
+        "is_symmetric" => flag(a.is_symmetric_within(tol)),
+        "is_skew_symmetric" => flag(a.is_skew_symmetric_within(tol)),
+        "is_diagonal" => flag(a.is_diagonal_within(tol)),
+        "is_lower_triangular" => flag(a.is_lower_triangular_within(tol)),
+        "is_upper_triangular" => flag(a.is_upper_triangular_within(tol)),
+        "is_invertible" => flag(a.is_invertible_within(tol)),
+        "elimination_steps" => Value::Text(steps_to_json(&a, &a.reduced_echelon_traced())),
+        "det_laplace" => {
+            let line = match arguments.first() {
+                Some(line) => Some(line.parse::<Line>()?),
+                None => None,
+            };
+            Value::Text(a.det_laplace(line)?.to_json())
+        }
+        "slice" => {
+            let rows = Range::<usize>::from_bytes(arguments[0].trim().as_bytes())?;
+            let cols = Range::<usize>::from_bytes(arguments[1].trim().as_bytes())?;
+            Value::Matrix(a.view(rows, cols)?.to_matrix())
+        }
+        "gram_schmidt" => {
+            let method = match arguments.first() {
+                Some(method) => method.parse()?,
+                None => GramSchmidt::Modified,
+            };
+            let vectors: Vec<Vector<T>> = a.rows().map(|row| Vector::from(row.to_vec())).collect();
+            let basis = gram_schmidt(&vectors, method)?;
+            Value::Matrix(Matrix::from_rows(
+                basis.into_iter().map(Vec::from).collect(),
+            )?)
+        }
+        _ => {
+            return Err(LinalgError::InvalidArgument(format!(
+                "Unknown operation '{}'",
+                op
+            )))
+        }
+    })
+}
+
+fn run_float(
+    command: &Command,
+    texts: &[String],
+    arguments: &[String],
+) -> Result<Value<f64>, LinalgError> {
+    let op = command.operation.as_str();
+    if !FLOAT_OPERATIONS.contains(&op) {
+        return run_generic(command, texts, arguments);
+    }
+    let arity = match op {
+        "least_squares" | "kabsch" | "estimate_affine" | "estimate_homography" => 2,
+        _ => 1,
+    };
+    check_arity(command, texts, arity)?;
+    let a = parse_matrix::<f64>(&texts[0])?;
+    let estimate = |(transform, residuals): (Matrix<f64>, Vec<f64>)| {
+        Value::List(vec![
+            Value::Matrix(transform),
+            Value::Vector(Vector::from(residuals)),
+        ])
+    };
+    Ok(match op {
+        "exp" => Value::Matrix(a.exp()?),
+        "least_squares" => Value::Vector(a.least_squares(&parse_vector(&texts[1])?)?),
+        "qr" => {
+            let (q, r) = a.qr_decomposition()?;
+            Value::List(vec![Value::Matrix(q), Value::Matrix(r)])
+        }
+        "svd" => {
+            let (u, sigma, v) = a.svd()?;
+            Value::List(vec![
+                Value::Matrix(u),
+                Value::Vector(sigma),
+                Value::Matrix(v),
+            ])
+        }
+        "homogeneous" => Value::Matrix(a.homogeneous()?),
+        "kabsch" => {
+            let with_scale = match arguments.first().map(String::as_str) {
+                Some("scale") => true,
+                None => false,
+                Some(other) => {
+                    return Err(LinalgError::InvalidArgument(format!(
+                        "Expected 'scale', found '{}'",
+                        other
+                    )))
+                }
+            };
+            let res = Matrix::kabsch(&a, &parse_matrix(&texts[1])?, with_scale)?;
+            Value::List(vec![
+                Value::Matrix(res.rotation),
+                Value::Vector(res.translation),
+                Value::Vector(Vector::from(vec![res.scale, res.rmsd])),
+            ])
+        }
+        "estimate_affine" => estimate(Matrix::estimate_affine(&a, &parse_matrix(&texts[1])?)?),
+        "estimate_homography" => {
+            estimate(Matrix::estimate_homography(&a, &parse_matrix(&texts[1])?)?)
+        }
+        _ => Value::Text(
+            a.to_exact_forms(&command.recovery)
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|form| form.to_string())
+                        .collect::<Vec<String>>()
+                        .join(",")
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+        ),
+    })
+}
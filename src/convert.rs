@@ -1,26 +1,52 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::common::*;
+use crate::error::{LinalgError, Position};
+use crate::format::{FormatScalar, NumberFormat};
+use crate::literal::parse_scalar;
+use crate::matrix::Matrix;
+use crate::tolerance::{frobenius_norm, Tolerance};
+
 pub trait Convertable: Sized {
     fn to_bytes(&self) -> Vec<u8>;
 
-    fn from_bytes(bytes: &[u8]) -> Result<Self, String>;
+    /// Like `to_bytes`, writing floating point numbers in `format`. Types
+    /// without any ignore it.
+    fn to_bytes_with(&self, _format: &NumberFormat) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError>;
+}
+
+/// Parses a whole argument, such as a single number or index, described as
+/// `what` in the error message.
+fn parse_argument<T: FromStr>(bytes: &[u8], what: &str) -> Result<T, LinalgError> {
+    let s = std::str::from_utf8(bytes)?;
+    s.trim()
+        .replace('\u{2212}', "-")
+        .parse::<T>()
+        .map_err(|_| LinalgError::parse(format!("Cannot parse '{}' as {}", s, what), None))
+}
+
+/// Parses one entry of a vector or matrix at `position`, see `Literal` for
+/// the accepted forms.
+pub fn parse_entry<T: Scalar>(token: &str, position: Position) -> Result<T, LinalgError> {
+    parse_scalar(token).map_err(|err| err.at(position))
 }
 
 impl Convertable for f64 {
     fn to_bytes(&self) -> Vec<u8> {
-        self.to_string().as_bytes().to_vec()
+        self.to_bytes_with(&NumberFormat::ROUND_TRIP)
     }
 
-    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
-        let res_str = std::str::from_utf8(bytes);
-        match res_str {
-            Ok(str) => {
-                let res_float = str.parse::<f64>();
-                match res_float {
-                    Ok(float) => Ok(float),
-                    Err(err) => Err(err.to_string()),
-                }
-            }
-            Err(err) => Err(err.to_string()),
-        }
+    fn to_bytes_with(&self, format: &NumberFormat) -> Vec<u8> {
+        format.format(*self).into_bytes()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        parse_scalar(std::str::from_utf8(bytes)?)
     }
 }
 
@@ -29,18 +55,8 @@ impl Convertable for i64 {
         self.to_string().as_bytes().to_vec()
     }
 
-    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
-        let res_str = std::str::from_utf8(bytes);
-        match res_str {
-            Ok(str) => {
-                let res_int = str.parse::<i64>();
-                match res_int {
-                    Ok(int) => Ok(int),
-                    Err(err) => Err(err.to_string()),
-                }
-            }
-            Err(err) => Err(err.to_string()),
-        }
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        parse_argument(bytes, "an integer")
     }
 }
 
@@ -49,17 +65,70 @@ impl Convertable for usize {
         self.to_string().as_bytes().to_vec()
     }
 
-    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
-        let res_str = std::str::from_utf8(bytes);
-        match res_str {
-            Ok(str) => {
-                let res_index = str.parse::<usize>();
-                match res_index {
-                    Ok(index) => Ok(index),
-                    Err(err) => Err(err.to_string()),
-                }
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        parse_argument(bytes, "an index")
+    }
+}
+
+impl Convertable for bool {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        parse_argument(bytes, "true or false")
+    }
+}
+
+impl Convertable for Tolerance {
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Tolerance::Absolute(tol) => format!("abs:{}", tol).into_bytes(),
+            Tolerance::Relative(tol) => format!("rel:{}", tol).into_bytes(),
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        std::str::from_utf8(bytes)?.parse()
+    }
+}
+
+impl Convertable for Range<usize> {
+    fn to_bytes(&self) -> Vec<u8> {
+        format!("{}..{}", self.start, self.end).as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        let res_str = std::str::from_utf8(bytes)?;
+        match res_str.split_once("..") {
+            Some((start, end)) => {
+                let start = usize::from_bytes(start.as_bytes())?;
+                let end = usize::from_bytes(end.as_bytes())?;
+                Ok(start..end)
             }
-            Err(err) => Err(err.to_string()),
+            None => Err(LinalgError::parse(
+                format!("Range '{}' must be of the form start..end", res_str),
+                None,
+            )),
         }
     }
 }
+
+/// A matrix as a JSON array of rows, with entries as strings so that exact
+/// values such as fractions survive. Floating point entries are written in
+/// the default `NumberFormat`, as the JSON is meant for display.
+pub fn matrix_to_json<T: Scalar>(mat: &Matrix<T>) -> String {
+    let format = NumberFormat::default();
+    let scale = frobenius_norm(mat.as_slice());
+    let rows: Vec<String> = mat
+        .rows()
+        .map(|row| {
+            let entries: Vec<String> = row
+                .iter()
+                .map(|x| format!("\"{}\"", x.format_scaled(&format, scale)))
+                .collect();
+            format!("[{}]", entries.join(","))
+        })
+        .collect();
+    format!("[{}]", rows.join(","))
+}
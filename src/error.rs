@@ -0,0 +1,150 @@
+use std::fmt;
+
+/// Where in the input a parse error occurred, counted from zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    /// An entry of a vector or list.
+    Entry(usize),
+    /// An entry of a matrix.
+    Cell(usize, usize),
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Position::Entry(i) => write!(f, "entry {}", i + 1),
+            Position::Cell(i, j) => write!(f, "row {}, column {}", i + 1, j + 1),
+        }
+    }
+}
+
+/// Everything that can go wrong in this crate. Plugin functions render it
+/// into the message Typst shows.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinalgError {
+    /// The shapes of two operands do not fit together. Vectors have shape
+    /// `(dim, 1)`.
+    DimensionMismatch {
+        operation: &'static str,
+        left: (usize, usize),
+        right: (usize, usize),
+    },
+    NotSquare {
+        operation: &'static str,
+        shape: (usize, usize),
+    },
+    Singular {
+        operation: &'static str,
+    },
+    IndexOutOfBounds {
+        what: &'static str,
+        index: usize,
+        len: usize,
+    },
+    Parse {
+        message: String,
+        position: Option<Position>,
+    },
+    NoConvergence {
+        method: &'static str,
+        iterations: usize,
+    },
+    /// An argument outside the domain of the operation, for example the zero
+    /// vector as a rotation axis.
+    InvalidArgument(String),
+}
+
+impl LinalgError {
+    pub fn invalid(message: &str) -> Self {
+        LinalgError::InvalidArgument(message.to_owned())
+    }
+
+    pub fn mismatch(operation: &'static str, left: (usize, usize), right: (usize, usize)) -> Self {
+        LinalgError::DimensionMismatch {
+            operation,
+            left,
+            right,
+        }
+    }
+
+    pub fn not_square(operation: &'static str, shape: (usize, usize)) -> Self {
+        LinalgError::NotSquare { operation, shape }
+    }
+
+    pub fn out_of_bounds(what: &'static str, index: usize, len: usize) -> Self {
+        LinalgError::IndexOutOfBounds { what, index, len }
+    }
+
+    pub fn parse(message: String, position: Option<Position>) -> Self {
+        LinalgError::Parse { message, position }
+    }
+
+    /// Moves a parse error found in a single entry to `position`.
+    pub fn at(self, position: Position) -> Self {
+        match self {
+            LinalgError::Parse { message, .. } => LinalgError::Parse {
+                message,
+                position: Some(position),
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for LinalgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinalgError::DimensionMismatch {
+                operation,
+                left,
+                right,
+            } => write!(
+                f,
+                "Cannot {} shapes {}×{} and {}×{}",
+                operation, left.0, left.1, right.0, right.1
+            ),
+            LinalgError::NotSquare { operation, shape } => write!(
+                f,
+                "Cannot {} a non-square {}×{} matrix",
+                operation, shape.0, shape.1
+            ),
+            LinalgError::Singular { operation } => {
+                write!(f, "Cannot {}: the matrix is singular", operation)
+            }
+            LinalgError::IndexOutOfBounds { what, index, len } => write!(
+                f,
+                "{} index {} is out of bounds for length {}",
+                what, index, len
+            ),
+            LinalgError::Parse {
+                message,
+                position: Some(position),
+            } => write!(f, "{} at {}", message, position),
+            LinalgError::Parse {
+                message,
+                position: None,
+            } => write!(f, "{}", message),
+            LinalgError::NoConvergence { method, iterations } => write!(
+                f,
+                "{} did not converge within {} iterations",
+                method, iterations
+            ),
+            LinalgError::InvalidArgument(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for LinalgError {}
+
+/// Plugin functions report errors to Typst as strings.
+impl From<LinalgError> for String {
+    fn from(err: LinalgError) -> Self {
+        err.to_string()
+    }
+}
+
+impl From<std::str::Utf8Error> for LinalgError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        LinalgError::parse(format!("Input is not valid UTF-8: {}", err), None)
+    }
+}
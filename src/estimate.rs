@@ -0,0 +1,172 @@
+use crate::error::LinalgError;
+use crate::matrix::Matrix;
+use crate::tolerance::{frobenius_norm, Tolerance};
+use crate::vector::Vector;
+
+/// The similarity moving the centroid of `points` to the origin and scaling
+/// their mean distance from it to √2, as suggested by Hartley, "In defense of
+/// the eight-point algorithm" (1997). Fitting in these coordinates keeps the
+/// equations well conditioned whatever units the points are given in.
+fn normalising_transform(points: &Matrix<f64>) -> Result<Matrix<f64>, LinalgError> {
+    let n = points.nrows() as f64;
+    let cx = points.rows().map(|p| p[0]).sum::<f64>() / n;
+    let cy = points.rows().map(|p| p[1]).sum::<f64>() / n;
+    let mean_dist = points
+        .rows()
+        .map(|p| (p[0] - cx).hypot(p[1] - cy))
+        .sum::<f64>()
+        / n;
+    if mean_dist == 0.0 {
+        return Err(LinalgError::invalid("Points must not all coincide"));
+    }
+    let s = std::f64::consts::SQRT_2 / mean_dist;
+    Matrix::from_rows(vec![
+        vec![s, 0.0, -s * cx],
+        vec![0.0, s, -s * cy],
+        vec![0.0, 0.0, 1.0],
+    ])
+}
+
+fn check_correspondences(
+    source: &Matrix<f64>,
+    target: &Matrix<f64>,
+    min_pairs: usize,
+) -> Result<(), LinalgError> {
+    if source.ncols() != 2 || target.ncols() != 2 {
+        return Err(LinalgError::invalid(
+            "Corresponding points must have two coordinates",
+        ));
+    }
+    if source.nrows() != target.nrows() {
+        return Err(LinalgError::invalid(
+            "Source and target must have the same number of points",
+        ));
+    }
+    if source.nrows() < min_pairs {
+        return Err(LinalgError::InvalidArgument(format!(
+            "At least {} point pairs are needed",
+            min_pairs
+        )));
+    }
+    Ok(())
+}
+
+/// The distance between each mapped source point and its target.
+fn reprojection_residuals(
+    transform: &Matrix<f64>,
+    source: &Matrix<f64>,
+    target: &Matrix<f64>,
+) -> Result<Vec<f64>, LinalgError> {
+    let mapped = transform.transform_points_2d(source)?;
+    Ok(mapped
+        .rows()
+        .zip(target.rows())
+        .map(|(p, q)| (p[0] - q[0]).hypot(p[1] - q[1]))
+        .collect())
+}
+
+/// Fits a transform in normalised coordinates and maps it back, so that the
+/// result acts on the original points.
+fn denormalise(
+    fitted: &Matrix<f64>,
+    source_norm: &Matrix<f64>,
+    target_norm: &Matrix<f64>,
+) -> Result<Matrix<f64>, LinalgError> {
+    Ok(&(&target_norm.inverse()? * fitted) * source_norm)
+}
+
+impl Matrix<f64> {
+    /// The 3×3 homogeneous affine transform sending each row of `source` to
+    /// the matching row of `target`, together with the reprojection residuals.
+    ///
+    /// Three pairs of non-collinear points determine the transform exactly;
+    /// with more pairs it is fitted by least squares.
+    pub fn estimate_affine(
+        source: &Matrix<f64>,
+        target: &Matrix<f64>,
+    ) -> Result<(Self, Vec<f64>), LinalgError> {
+        check_correspondences(source, target, 3)?;
+        let source_norm = normalising_transform(source)?;
+        let target_norm = normalising_transform(target)?;
+        let src = source_norm.transform_points_2d(source)?;
+        let dst = target_norm.transform_points_2d(target)?;
+
+        // Both output coordinates share the design matrix rows `x, y, 1`.
+        let design = Matrix::from_rows(src.rows().map(|p| vec![p[0], p[1], 1.0]).collect())?;
+        let mut fitted = Matrix::id(3);
+        for coord in 0..2 {
+            let rhs = Vector::from(dst.rows().map(|q| q[coord]).collect::<Vec<f64>>());
+            let coefficients = design
+                .least_squares(&rhs)
+                .map_err(|_| LinalgError::invalid("Source points must not be collinear"))?;
+            fitted[coord].copy_from_slice(&coefficients.entries);
+        }
+
+        let transform = denormalise(&fitted, &source_norm, &target_norm)?;
+        let residuals = reprojection_residuals(&transform, source, target)?;
+        Ok((transform, residuals))
+    }
+
+    /// The projective homography sending each row of `source` to the matching
+    /// row of `target`, together with the reprojection residuals. It is scaled
+    /// so that its bottom right entry is 1, or to unit norm with a positive
+    /// largest entry if that entry is zero, as for homographies sending the
+    /// origin to infinity.
+    ///
+    /// Uses the direct linear transform with at least four pairs, no three
+    /// of which may be collinear: the entries are the right singular vector
+    /// of the homogeneous system with the smallest singular value.
+    pub fn estimate_homography(
+        source: &Matrix<f64>,
+        target: &Matrix<f64>,
+    ) -> Result<(Self, Vec<f64>), LinalgError> {
+        check_correspondences(source, target, 4)?;
+        let source_norm = normalising_transform(source)?;
+        let target_norm = normalising_transform(target)?;
+        let src = source_norm.transform_points_2d(source)?;
+        let dst = target_norm.transform_points_2d(target)?;
+
+        // Each pair (x, y) -> (u, v) gives two linear equations in the nine
+        // entries of H. With four pairs a zero row is added, so that the thin
+        // SVD still has a ninth right singular vector.
+        let mut equations = Vec::with_capacity((2 * src.nrows()).max(9));
+        for (p, q) in src.rows().zip(dst.rows()) {
+            let (x, y, u, v) = (p[0], p[1], q[0], q[1]);
+            equations.push(vec![x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, -u]);
+            equations.push(vec![0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, -v]);
+        }
+        equations.resize(equations.len().max(9), vec![0.0; 9]);
+        let (_, sigma, v) = Matrix::from_rows(equations)?.svd()?;
+        // A second vanishing singular value leaves a family of solutions.
+        if sigma[7] <= Tolerance::default().threshold(sigma[0]) {
+            return Err(LinalgError::invalid(
+                "Points are degenerate; no three may be collinear",
+            ));
+        }
+        let h: Vec<f64> = v.rows().map(|row| row[8]).collect();
+        let fitted = Matrix::from_vec(3, 3, h)?;
+
+        let mut transform = denormalise(&fitted, &source_norm, &target_norm)?;
+        let norm = frobenius_norm(transform.as_slice());
+        let scale = match transform[2][2] {
+            corner if corner.abs() > Tolerance::default().threshold(norm) => corner,
+            // The sign is fixed by making the largest entry positive.
+            _ => {
+                let largest = transform.as_slice().iter().copied().fold(0.0, |a: f64, b| {
+                    if b.abs() > a.abs() {
+                        b
+                    } else {
+                        a
+                    }
+                });
+                norm.copysign(largest)
+            }
+        };
+        transform
+            .as_mut_slice()
+            .iter_mut()
+            .for_each(|x| *x /= scale);
+        let residuals = reprojection_residuals(&transform, source, target)?;
+        Ok((transform, residuals))
+    }
+}
@@ -0,0 +1,220 @@
+use std::str::FromStr;
+
+use num::complex::Complex64;
+
+use crate::common::*;
+use crate::error::LinalgError;
+use crate::tolerance::{frobenius_norm, Approximate, Tolerance};
+
+/// How many digits of a floating point number are printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Digits {
+    /// As many as needed to read the number back exactly.
+    Shortest,
+    /// This many significant digits, without trailing zeros.
+    Significant(usize),
+    /// Exactly this many digits after the decimal point.
+    Decimals(usize),
+}
+
+/// How floating point numbers are written for display. Plugin results are
+/// passed on in `ROUND_TRIP` instead, so that chained calls lose nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    pub digits: Digits,
+    /// Numbers within this distance of a nonzero integer, relative to the
+    /// larger of their own size and 1, are printed as that integer. Numbers
+    /// negligible next to the matrix or vector they belong to, under the
+    /// default `Tolerance`, are printed as 0; this sends rounding noise to 0
+    /// without zeroing matrices that are small as a whole. Zero disables
+    /// both.
+    pub snap: f64,
+    /// Nonzero numbers of absolute value below the first or at least the
+    /// second bound are printed in scientific notation, such as `1.5e-7`.
+    pub scientific: (f64, f64),
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            digits: Digits::Significant(12),
+            snap: 1e-12,
+            scientific: (1e-5, 1e15),
+        }
+    }
+}
+
+/// Removes trailing zeros after the decimal point, and the point itself if
+/// nothing is left after it.
+fn trim_fraction(s: &str) -> &str {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        s
+    }
+}
+
+impl NumberFormat {
+    /// The shortest form that reads back as the same number.
+    pub const ROUND_TRIP: NumberFormat = NumberFormat {
+        digits: Digits::Shortest,
+        snap: 0.0,
+        scientific: (1e-5, 1e15),
+    };
+
+    /// Formats a number on its own, which is never negligible next to itself.
+    pub fn format(&self, x: f64) -> String {
+        self.format_scaled(x, x.abs())
+    }
+
+    /// Formats a number belonging to a matrix or vector of norm `scale`.
+    pub fn format_scaled(&self, x: f64, scale: f64) -> String {
+        if !x.is_finite() {
+            return x.to_string();
+        }
+        let mut x = x;
+        if self.snap > 0.0 && x.abs() <= Tolerance::default().threshold(scale) {
+            x = 0.0;
+        } else if x.round() != 0.0 && (x - x.round()).abs() <= self.snap * x.abs().max(1.0) {
+            x = x.round();
+        }
+        if x == 0.0 {
+            // Also turns -0 into 0.
+            return self.format_fixed(0.0);
+        }
+        if x.abs() < self.scientific.0 || x.abs() >= self.scientific.1 {
+            self.format_scientific(x)
+        } else {
+            self.format_fixed(x)
+        }
+    }
+
+    fn format_scientific(&self, x: f64) -> String {
+        let s = match self.digits {
+            Digits::Shortest => format!("{:e}", x),
+            Digits::Significant(digits) => format!("{:.*e}", digits.max(1) - 1, x),
+            Digits::Decimals(decimals) => return format!("{:.*e}", decimals, x),
+        };
+        let (mantissa, exponent) = s.split_once('e').unwrap_or((&s, "0"));
+        format!("{}e{}", trim_fraction(mantissa), exponent)
+    }
+
+    fn format_fixed(&self, x: f64) -> String {
+        match self.digits {
+            Digits::Shortest => x.to_string(),
+            Digits::Significant(digits) => {
+                // Rounding in scientific notation first settles the exponent,
+                // which may grow, as in 9.9996 -> 10.00, and zeroes the digits
+                // of large numbers past the significant ones.
+                let digits = digits.max(1);
+                let rounded = format!("{:.*e}", digits - 1, x);
+                let exponent: i32 = rounded
+                    .split_once('e')
+                    .and_then(|(_, e)| e.parse().ok())
+                    .unwrap_or(0);
+                let value: f64 = rounded.parse().unwrap_or(x);
+                let decimals = (digits as i32 - 1 - exponent).max(0) as usize;
+                trim_fraction(&format!("{:.*}", decimals, value)).to_owned()
+            }
+            Digits::Decimals(decimals) => {
+                let s = format!("{:.*}", decimals, x);
+                // Numbers that round to zero lose their sign, as in -0.00.
+                match s.strip_prefix('-') {
+                    Some(magnitude) if magnitude.bytes().all(|b| b == b'0' || b == b'.') => {
+                        magnitude.to_owned()
+                    }
+                    _ => s,
+                }
+            }
+        }
+    }
+}
+
+/// Parses comma separated options, each overriding the default:
+/// `sig:n` or `dec:n` for the digits (`sig:0` for the shortest exact form),
+/// `snap:x` for the snapping distance and `sci:lo..hi` for the range printed
+/// without scientific notation. The empty string gives the default.
+impl FromStr for NumberFormat {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut format = NumberFormat::default();
+        for option in s.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            let err = || LinalgError::parse(format!("Invalid format option '{}'", option), None);
+            let (key, value) = option.split_once(':').ok_or_else(err)?;
+            let value = value.trim();
+            match key.trim() {
+                "sig" => {
+                    format.digits = match value.parse().map_err(|_| err())? {
+                        0 => Digits::Shortest,
+                        digits => Digits::Significant(digits),
+                    }
+                }
+                "dec" => format.digits = Digits::Decimals(value.parse().map_err(|_| err())?),
+                "snap" => format.snap = value.parse().map_err(|_| err())?,
+                "sci" => {
+                    let (below, above) = value.split_once("..").ok_or_else(err)?;
+                    format.scientific = (
+                        below.trim().parse().map_err(|_| err())?,
+                        above.trim().parse().map_err(|_| err())?,
+                    );
+                }
+                _ => return Err(err()),
+            }
+        }
+        Ok(format)
+    }
+}
+
+/// Scalars that can be written according to a `NumberFormat`. Exact scalars
+/// are written as they are.
+pub trait FormatScalar: Scalar {
+    /// Formats an entry of a matrix or vector of norm `scale`, see
+    /// `NumberFormat::format_scaled`.
+    fn format_scaled(&self, format: &NumberFormat, scale: f64) -> String;
+
+    /// Formats a scalar on its own.
+    fn format_with(&self, format: &NumberFormat) -> String {
+        self.format_scaled(format, self.magnitude())
+    }
+}
+
+impl<T: Scalar> FormatScalar for T {
+    default fn format_scaled(&self, _format: &NumberFormat, _scale: f64) -> String {
+        self.to_string()
+    }
+}
+
+impl FormatScalar for f64 {
+    fn format_scaled(&self, format: &NumberFormat, scale: f64) -> String {
+        format.format_scaled(*self, scale)
+    }
+}
+
+/// Written as `a+bi` like the `Display` of `Complex64`, dropping a part that
+/// formats as zero.
+impl FormatScalar for Complex64 {
+    fn format_scaled(&self, format: &NumberFormat, scale: f64) -> String {
+        let re = format.format_scaled(self.re, scale);
+        let im = format.format_scaled(self.im, scale);
+        let is_zero = |s: &str| s.parse::<f64>() == Ok(0.0);
+        match (is_zero(&re), is_zero(&im)) {
+            (_, true) => re,
+            (true, false) => format!("{}i", im),
+            (false, false) => match im.strip_prefix('-') {
+                Some(abs) => format!("{}-{}i", re, abs),
+                None => format!("{}+{}i", re, im),
+            },
+        }
+    }
+}
+
+/// Formats `entries` of one matrix or vector, snapping to 0 relative to
+/// their norm.
+pub fn format_entries<T: Scalar>(entries: &[T], format: &NumberFormat) -> Vec<String> {
+    let scale = frobenius_norm(entries);
+    entries
+        .iter()
+        .map(|x| x.format_scaled(format, scale))
+        .collect()
+}
@@ -0,0 +1,141 @@
+use crate::common::*;
+
+/// The inner loops shared by multiplication, matrix-vector products and
+/// elimination, so that they can be specialised per scalar type.
+pub trait Kernel: Scalar {
+    /// Computes the sum of `a[i] * b[i]` over the common length of the slices.
+    fn dot(a: &[Self], b: &[Self]) -> Self;
+
+    /// Computes `y[i] += a * x[i]` over the common length of the slices.
+    fn axpy(y: &mut [Self], a: Self, x: &[Self]);
+
+    /// Computes `ys[r][i] += a[r] * x[i]` for four rows at once, loading every
+    /// entry of `x` only once. All slices must have the length of `x`.
+    fn axpy4(ys: [&mut [Self]; 4], a: [Self; 4], x: &[Self]);
+}
+
+fn dot_scalar<T: Scalar>(a: &[T], b: &[T]) -> T {
+    a.iter().zip(b.iter()).map(|(x, y)| *x * *y).sum()
+}
+
+fn axpy_scalar<T: Scalar>(y: &mut [T], a: T, x: &[T]) {
+    for (yi, xi) in y.iter_mut().zip(x.iter()) {
+        *yi += a * *xi;
+    }
+}
+
+fn axpy4_scalar<T: Scalar>(ys: [&mut [T]; 4], a: [T; 4], x: &[T]) {
+    let [y0, y1, y2, y3] = ys;
+    for (j, &xj) in x.iter().enumerate() {
+        y0[j] += a[0] * xj;
+        y1[j] += a[1] * xj;
+        y2[j] += a[2] * xj;
+        y3[j] += a[3] * xj;
+    }
+}
+
+impl<T: Scalar> Kernel for T {
+    default fn dot(a: &[T], b: &[T]) -> T {
+        dot_scalar(a, b)
+    }
+
+    default fn axpy(y: &mut [T], a: T, x: &[T]) {
+        axpy_scalar(y, a, x)
+    }
+
+    default fn axpy4(ys: [&mut [T]; 4], a: [T; 4], x: &[T]) {
+        axpy4_scalar(ys, a, x)
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+use simd128::{axpy as axpy_f64, axpy4 as axpy4_f64, dot as dot_f64};
+
+#[cfg(not(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")))]
+use self::{axpy4_scalar as axpy4_f64, axpy_scalar as axpy_f64, dot_scalar as dot_f64};
+
+impl Kernel for f64 {
+    fn dot(a: &[f64], b: &[f64]) -> f64 {
+        dot_f64(a, b)
+    }
+
+    fn axpy(y: &mut [f64], a: f64, x: &[f64]) {
+        axpy_f64(y, a, x)
+    }
+
+    fn axpy4(ys: [&mut [f64]; 4], a: [f64; 4], x: &[f64]) {
+        axpy4_f64(ys, a, x)
+    }
+}
+
+/// Two-lane f64 versions of the kernels, for wasm runtimes with the `simd128`
+/// proposal. Enabled by building with `--features simd` and
+/// `RUSTFLAGS="-C target-feature=+simd128"`.
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+mod simd128 {
+    use core::arch::wasm32::*;
+
+    pub fn dot(a: &[f64], b: &[f64]) -> f64 {
+        let len = a.len().min(b.len());
+        let mut acc = f64x2_splat(0.0);
+        for k in 0..len / 2 {
+            // SAFETY: `2 * k + 1 < len`, so both lanes are inside both slices,
+            // and wasm loads have no alignment requirement.
+            let (x, y) = unsafe {
+                (
+                    v128_load(a.as_ptr().add(2 * k) as *const v128),
+                    v128_load(b.as_ptr().add(2 * k) as *const v128),
+                )
+            };
+            acc = f64x2_add(acc, f64x2_mul(x, y));
+        }
+        let mut sum = f64x2_extract_lane::<0>(acc) + f64x2_extract_lane::<1>(acc);
+        if len % 2 == 1 {
+            sum += a[len - 1] * b[len - 1];
+        }
+        sum
+    }
+
+    pub fn axpy(y: &mut [f64], a: f64, x: &[f64]) {
+        let len = y.len().min(x.len());
+        let scale = f64x2_splat(a);
+        for k in 0..len / 2 {
+            // SAFETY: as in `dot`, both lanes of every load and store are in bounds.
+            unsafe {
+                let y_ptr = y.as_mut_ptr().add(2 * k) as *mut v128;
+                let x_pair = v128_load(x.as_ptr().add(2 * k) as *const v128);
+                v128_store(y_ptr, f64x2_add(v128_load(y_ptr), f64x2_mul(scale, x_pair)));
+            }
+        }
+        if len % 2 == 1 {
+            y[len - 1] += a * x[len - 1];
+        }
+    }
+
+    pub fn axpy4(mut ys: [&mut [f64]; 4], a: [f64; 4], x: &[f64]) {
+        let len = x.len();
+        assert!(ys.iter().all(|y| y.len() == len));
+        let scales = [
+            f64x2_splat(a[0]),
+            f64x2_splat(a[1]),
+            f64x2_splat(a[2]),
+            f64x2_splat(a[3]),
+        ];
+        for k in 0..len / 2 {
+            // SAFETY: every row has the length of `x`, so as in `dot` both
+            // lanes of every load and store are in bounds.
+            unsafe {
+                let x_pair = v128_load(x.as_ptr().add(2 * k) as *const v128);
+                for (y, &scale) in ys.iter_mut().zip(&scales) {
+                    let y_ptr = y.as_mut_ptr().add(2 * k) as *mut v128;
+                    v128_store(y_ptr, f64x2_add(v128_load(y_ptr), f64x2_mul(scale, x_pair)));
+                }
+            }
+        }
+        if len % 2 == 1 {
+            for (y, &c) in ys.into_iter().zip(&a) {
+                y[len - 1] += c * x[len - 1];
+            }
+        }
+    }
+}
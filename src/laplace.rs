@@ -0,0 +1,195 @@
+use std::str::FromStr;
+
+use crate::common::*;
+use crate::convert::matrix_to_json;
+use crate::error::LinalgError;
+use crate::format::{FormatScalar, NumberFormat};
+use crate::matrix::Matrix;
+
+/// Largest size expanded by `det_laplace`. The expansion has up to n! leaves,
+/// and its JSON for a dense 8×8 matrix is already a few megabytes.
+pub const MAX_LAPLACE_SIZE: usize = 8;
+
+/// The row or column a cofactor expansion runs along, indexed from zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+/// Parses `row,i` or `col,j`.
+impl FromStr for Line {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err =
+            || LinalgError::parse(format!("Expected 'row,i' or 'col,j', found '{}'", s), None);
+        let (kind, index) = s.split_once(',').ok_or_else(err)?;
+        let index = index.trim().parse::<usize>().map_err(|_| err())?;
+        match kind.trim() {
+            "row" => Ok(Line::Row(index)),
+            "col" => Ok(Line::Column(index)),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// One term `sign · entry · det(minor)` of a cofactor expansion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cofactor<T: Scalar> {
+    pub row: usize,
+    pub col: usize,
+    pub entry: T,
+    /// `(-1)^(row + col)`, as 1 or -1.
+    pub sign: i8,
+    pub minor: Expansion<T>,
+}
+
+/// The determinant of `matrix` worked out by cofactor expansion. Matrices of
+/// size 2 or less are leaves, evaluated directly, and have no `line`.
+/// Terms whose entry is zero are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion<T: Scalar> {
+    pub matrix: Matrix<T>,
+    pub line: Option<Line>,
+    pub terms: Vec<Cofactor<T>>,
+    pub value: T,
+}
+
+impl<T: Scalar> Matrix<T> {
+    /// The first row or column with the most zeros, preferring rows. The
+    /// matrix must be square and nonempty.
+    fn sparsest_line(&self) -> Line {
+        let row_zeros = |i: usize| self[i].iter().filter(|x| x.is_zero()).count();
+        let col_zeros = |j: usize| self.rows().filter(|row| row[j].is_zero()).count();
+        let mut best = (row_zeros(0), Line::Row(0));
+        for i in 1..self.nrows() {
+            if row_zeros(i) > best.0 {
+                best = (row_zeros(i), Line::Row(i));
+            }
+        }
+        for j in 0..self.ncols() {
+            if col_zeros(j) > best.0 {
+                best = (col_zeros(j), Line::Column(j));
+            }
+        }
+        best.1
+    }
+
+    /// Computes the determinant by cofactor expansion along `line`, or along
+    /// the row or column with the most zeros if none is given, in which case
+    /// matrices up to 2×2 are evaluated directly. Minors are always expanded
+    /// along their sparsest line. Only ring operations are
+    /// used, so the result is exact over integers and `Fraction`. Matrices
+    /// larger than `MAX_LAPLACE_SIZE` are rejected.
+    pub fn det_laplace(&self, line: Option<Line>) -> Result<Expansion<T>, LinalgError> {
+        if !self.is_square() {
+            return Err(LinalgError::not_square(
+                "expand the determinant of",
+                self.shape(),
+            ));
+        }
+        let n = self.nrows();
+        if n > MAX_LAPLACE_SIZE {
+            return Err(LinalgError::invalid(&format!(
+                "Cofactor expansion is limited to {}×{} matrices, as its size grows as n!; \
+                 use det for a {}×{} matrix",
+                MAX_LAPLACE_SIZE, MAX_LAPLACE_SIZE, n, n
+            )));
+        }
+        match line {
+            Some(Line::Row(i)) if i >= n => return Err(LinalgError::out_of_bounds("Row", i, n)),
+            Some(Line::Column(j)) if j >= n => {
+                return Err(LinalgError::out_of_bounds("Column", j, n))
+            }
+            // Small matrices are leaves, unless the caller names a line.
+            None if n <= 2 => {
+                let value = match n {
+                    0 => T::one(),
+                    1 => self[0][0],
+                    _ => self[0][0] * self[1][1] - self[0][1] * self[1][0],
+                };
+                return Ok(Expansion {
+                    matrix: self.clone(),
+                    line: None,
+                    terms: vec![],
+                    value,
+                });
+            }
+            _ => {}
+        }
+
+        let line = line.unwrap_or_else(|| self.sparsest_line());
+        let positions: Vec<(usize, usize)> = match line {
+            Line::Row(i) => (0..n).map(|j| (i, j)).collect(),
+            Line::Column(j) => (0..n).map(|i| (i, j)).collect(),
+        };
+
+        let mut terms = vec![];
+        let mut value = T::zero();
+        for (i, j) in positions {
+            let entry = self[i][j];
+            if entry.is_zero() {
+                continue;
+            }
+            let minor = self.submatrix(i, j)?.det_laplace(None)?;
+            let sign = if (i + j) % 2 == 0 { 1 } else { -1 };
+            let term = entry * minor.value;
+            if sign == 1 {
+                value += term;
+            } else {
+                value -= term;
+            }
+            terms.push(Cofactor {
+                row: i,
+                col: j,
+                entry,
+                sign,
+                minor,
+            });
+        }
+        Ok(Expansion {
+            matrix: self.clone(),
+            line: Some(line),
+            terms,
+            value,
+        })
+    }
+}
+
+impl<T: Scalar> Expansion<T> {
+    /// Serialises the tree as JSON: `{"matrix": m, "value": "3", "line": {"row": 0},
+    /// "terms": [{"row": 0, "col": 1, "entry": "2", "sign": -1, "minor": {...}}]}`.
+    /// Entries and values are strings so that fractions survive, with floating
+    /// point ones in the default `NumberFormat`. Indices start at zero, and
+    /// leaves have `"line": null`.
+    pub fn to_json(&self) -> String {
+        let format = NumberFormat::default();
+        let line = match self.line {
+            Some(Line::Row(i)) => format!("{{\"row\":{}}}", i),
+            Some(Line::Column(j)) => format!("{{\"col\":{}}}", j),
+            None => "null".to_owned(),
+        };
+        let terms: Vec<String> = self
+            .terms
+            .iter()
+            .map(|term| {
+                format!(
+                    "{{\"row\":{},\"col\":{},\"entry\":\"{}\",\"sign\":{},\"minor\":{}}}",
+                    term.row,
+                    term.col,
+                    term.entry.format_with(&format),
+                    term.sign,
+                    term.minor.to_json()
+                )
+            })
+            .collect();
+        format!(
+            "{{\"matrix\":{},\"value\":\"{}\",\"line\":{},\"terms\":[{}]}}",
+            matrix_to_json(&self.matrix),
+            self.value.format_with(&format),
+            line,
+            terms.join(",")
+        )
+    }
+}
@@ -1,14 +1,47 @@
 #![allow(dead_code)]
 #![feature(min_specialization)]
+use fraction::Fraction;
+use num::complex::Complex64;
 use wasm_minimal_protocol::*;
 
+mod align;
 mod common;
 mod convert;
+mod error;
+mod estimate;
+mod format;
+mod kernel;
+mod laplace;
+mod literal;
+mod markup;
 mod matrix;
+mod multiply;
+mod orthogonal;
+mod quaternion;
+mod rational;
+mod rotation;
+mod smatrix;
+mod sparse;
+mod tolerance;
+mod trace;
+mod transform;
 mod vector;
+mod view;
 
+use common::Scalar;
 use convert::Convertable;
+use error::LinalgError;
+use format::NumberFormat;
+use markup::RenderOptions;
 use matrix::*;
+use orthogonal::*;
+use quaternion::Quaternion;
+use rational::Recovery;
+use rotation::EulerConvention;
+use smatrix::*;
+use sparse::*;
+use std::ops::Range;
+use tolerance::Tolerance;
 use vector::*;
 
 initiate_protocol!();
@@ -35,13 +68,48 @@ macro_rules! unary_err {
     };
 }
 
+/// Like `unary!`, with a tolerance as second argument: `abs:tol`, `rel:tol`,
+/// or empty for the default.
+macro_rules! unary_tol {
+    ($name: tt, $content: tt) => {
+        #[wasm_func]
+        pub fn $name(arg: &[u8], tol: &[u8]) -> Result<Vec<u8>, String> {
+            let mat = Matrix::from_bytes(arg)?;
+            let tol = Tolerance::from_bytes(tol)?;
+            let res = $content(mat, tol);
+            Ok(res.to_bytes())
+        }
+    };
+}
+
 macro_rules! binary {
     ($name: tt, $content: tt) => {
         #[wasm_func]
         pub fn $name(arg1: &[u8], arg2: &[u8]) -> Result<Vec<u8>, String> {
             let mat1 = Matrix::from_bytes(arg1)?;
             let mat2 = Matrix::from_bytes(arg2)?;
-            let res = $content(mat1, mat2);
+            let res = $content(mat1, mat2)?;
+            Ok(res.to_bytes())
+        }
+    };
+}
+
+macro_rules! builder {
+    ($name: tt, $content: tt, $($arg: ident),*) => {
+        #[wasm_func]
+        pub fn $name($($arg: &[u8]),*) -> Result<Vec<u8>, String> {
+            let res = $content($(f64::from_bytes($arg)?),*);
+            Ok(res.to_bytes())
+        }
+    };
+}
+
+/// Like `builder!`, for builders that reject some arguments.
+macro_rules! builder_err {
+    ($name: tt, $content: tt, $($arg: ident),*) => {
+        #[wasm_func]
+        pub fn $name($($arg: &[u8]),*) -> Result<Vec<u8>, String> {
+            let res = $content($(f64::from_bytes($arg)?),*)?;
             Ok(res.to_bytes())
         }
     };
@@ -51,22 +119,72 @@ type RMatrix = Matrix<f64>;
 
 unary!(neg, { |m: RMatrix| -m });
 unary!(transpose, { |m: RMatrix| m.transpose() });
-unary!(echelon, { |m: RMatrix| m.echelon().0 });
-unary!(reduced_echelon, { |m: RMatrix| m.reduced_echelon() });
+unary_tol!(echelon, { |m: RMatrix, tol| m.echelon_within(tol).0 });
+unary_tol!(reduced_echelon, {
+    |m: RMatrix, tol| m.reduced_echelon_within(tol)
+});
+unary_tol!(rank, { |m: RMatrix, tol| m.rank_within(tol) });
+unary_tol!(is_symmetric, {
+    |m: RMatrix, tol| m.is_symmetric_within(tol)
+});
+unary_tol!(is_skew_symmetric, {
+    |m: RMatrix, tol| m.is_skew_symmetric_within(tol)
+});
+unary_tol!(is_diagonal, { |m: RMatrix, tol| m.is_diagonal_within(tol) });
+unary_tol!(is_lower_triangular, {
+    |m: RMatrix, tol| m.is_lower_triangular_within(tol)
+});
+unary_tol!(is_upper_triangular, {
+    |m: RMatrix, tol| m.is_upper_triangular_within(tol)
+});
+unary_tol!(is_invertible, {
+    |m: RMatrix, tol| m.is_invertible_within(tol)
+});
 
-unary_err!(det, { |m: RMatrix| m.det() });
 unary_err!(trace, { |m: RMatrix| m.trace() });
-unary_err!(inverse, { |m: RMatrix| m.inverse() });
 unary_err!(exp, { |m: RMatrix| m.exp() });
 
-binary!(add, { |m1: RMatrix, m2: RMatrix| m1 + m2 });
-binary!(sub, { |m1: RMatrix, m2: RMatrix| m1 - m2 });
-binary!(mul, { |m1: RMatrix, m2: RMatrix| m1 * m2 });
+/// Like `mul`, 2×2 up to 4×4 matrices are handled on the stack.
+#[wasm_func]
+pub fn det(mat_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if let Some(res) = det_small::<f64>(mat_bytes) {
+        return Ok(res?);
+    }
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    Ok(mat.det()?.to_bytes())
+}
+
+#[wasm_func]
+pub fn inverse(mat_bytes: &[u8], tol_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let tol = Tolerance::from_bytes(tol_bytes)?;
+    if let Some(res) = inverse_small::<f64>(mat_bytes, tol) {
+        return Ok(res?);
+    }
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    Ok(mat.inverse_within(tol)?.to_bytes())
+}
+
+binary!(add, { |m1: RMatrix, m2: RMatrix| m1.checked_add(&m2) });
+binary!(sub, { |m1: RMatrix, m2: RMatrix| m1.checked_sub(&m2) });
+/// Products of 2×2 up to 4×4 matrices are parsed into and computed on the
+/// stack.
+#[wasm_func]
+pub fn mul(m1_bytes: &[u8], m2_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if let Some(res) = mul_small::<f64>(m1_bytes, m2_bytes) {
+        return Ok(res?);
+    }
+    let m1: RMatrix = Matrix::from_bytes(m1_bytes)?;
+    let m2: RMatrix = Matrix::from_bytes(m2_bytes)?;
+    Ok(m1.checked_mul(&m2)?.to_bytes())
+}
 
 #[wasm_func]
 pub fn pow(mat_bytes: &[u8], pow_bytes: &[u8]) -> Result<Vec<u8>, String> {
-    let mat = RMatrix::from_bytes(mat_bytes)?;
     let pow = i64::from_bytes(pow_bytes)?;
+    if let Some(res) = pow_small::<f64>(mat_bytes, pow) {
+        return Ok(res?);
+    }
+    let mat = RMatrix::from_bytes(mat_bytes)?;
     let res = mat.powi(pow)?;
     Ok(res.to_bytes())
 }
@@ -82,8 +200,605 @@ pub fn rowswap(mat_bytes: &[u8], r1_bytes: &[u8], r2_bytes: &[u8]) -> Result<Vec
 
 #[wasm_func]
 pub fn mul_vec(mat_bytes: &[u8], vec_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if let Some(res) = mul_vector_small::<f64>(mat_bytes, vec_bytes) {
+        return Ok(res?);
+    }
     let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
     let vec = Vector::from_bytes(vec_bytes)?;
-    let res = mat.mul_vector(&vec)?;
+    Ok(mat.mul_vector(&vec)?.to_bytes())
+}
+
+unary_err!(homogeneous, { |m: RMatrix| m.homogeneous() });
+
+builder!(rotation_2d, { |angle| RMatrix::rotation_2d(angle) }, angle);
+builder!(
+    rotation_x_3d,
+    { |angle| RMatrix::rotation_x_3d(angle) },
+    angle
+);
+builder!(
+    rotation_y_3d,
+    { |angle| RMatrix::rotation_y_3d(angle) },
+    angle
+);
+builder!(
+    rotation_z_3d,
+    { |angle| RMatrix::rotation_z_3d(angle) },
+    angle
+);
+builder!(
+    shear_3d,
+    { |xy, xz, yx, yz, zx, zy| RMatrix::shear_3d(xy, xz, yx, yz, zx, zy) },
+    xy,
+    xz,
+    yx,
+    yz,
+    zx,
+    zy
+);
+builder_err!(
+    perspective,
+    { |fov_y, aspect, near, far| RMatrix::perspective(fov_y, aspect, near, far) },
+    fov_y,
+    aspect,
+    near,
+    far
+);
+builder_err!(
+    frustum,
+    { |l, r, b, t, n, f| RMatrix::frustum(l, r, b, t, n, f) },
+    l,
+    r,
+    b,
+    t,
+    n,
+    f
+);
+builder_err!(
+    orthographic,
+    { |l, r, b, t, n, f| RMatrix::orthographic(l, r, b, t, n, f) },
+    l,
+    r,
+    b,
+    t,
+    n,
+    f
+);
+
+#[wasm_func]
+pub fn translation(offset_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let offset = Vector::from_bytes(offset_bytes)?;
+    Ok(RMatrix::translation(&offset).to_bytes())
+}
+
+#[wasm_func]
+pub fn scaling(factors_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let factors = Vector::from_bytes(factors_bytes)?;
+    Ok(RMatrix::scaling(&factors).to_bytes())
+}
+
+#[wasm_func]
+pub fn look_at(eye_bytes: &[u8], target_bytes: &[u8], up_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let eye = Vector::from_bytes(eye_bytes)?;
+    let target = Vector::from_bytes(target_bytes)?;
+    let up = Vector::from_bytes(up_bytes)?;
+    let res = RMatrix::look_at(&eye, &target, &up)?;
+    Ok(res.to_bytes())
+}
+
+#[wasm_func]
+pub fn rotation_axis_angle(axis_bytes: &[u8], angle_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let axis = Vector::from_bytes(axis_bytes)?;
+    let angle = f64::from_bytes(angle_bytes)?;
+    let res = RMatrix::rotation_axis_angle(&axis, angle)?;
     Ok(res.to_bytes())
 }
+
+/// Returns the axis followed by the angle, as `x,y,z,angle`.
+#[wasm_func]
+pub fn axis_angle(mat_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    let (axis, angle) = mat.to_axis_angle()?;
+    let mut res: Vec<f64> = axis.into();
+    res.push(angle);
+    Ok(Vector::from(res).to_bytes())
+}
+
+fn parse_convention(bytes: &[u8]) -> Result<EulerConvention, LinalgError> {
+    std::str::from_utf8(bytes)?.parse()
+}
+
+#[wasm_func]
+pub fn euler_to_matrix(angles_bytes: &[u8], convention_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let angles = match Vector::<f64>::from_bytes(angles_bytes)?.entries[..] {
+        [a, b, c] => [a, b, c],
+        _ => return Err("Exactly three Euler angles are needed".to_owned()),
+    };
+    let convention = parse_convention(convention_bytes)?;
+    Ok(RMatrix::from_euler(angles, convention).to_bytes())
+}
+
+#[wasm_func]
+pub fn matrix_to_euler(mat_bytes: &[u8], convention_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    let convention = parse_convention(convention_bytes)?;
+    let res = mat.to_euler(convention)?;
+    Ok(Vector::from(res.to_vec()).to_bytes())
+}
+
+#[wasm_func]
+pub fn quaternion_to_matrix(quat_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let quat = Quaternion::from_bytes(quat_bytes)?;
+    Ok(quat.to_rotation_matrix()?.to_bytes())
+}
+
+#[wasm_func]
+pub fn matrix_to_quaternion(mat_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    Ok(Quaternion::from_rotation_matrix(&mat)?.to_bytes())
+}
+
+/// Returns `frames` rotation matrices turning evenly from `start` to `end`,
+/// both included, separated by `|`. The matrices have the size of `start`,
+/// which may be 3×3 or a 4×4 homogeneous rotation.
+#[wasm_func]
+pub fn slerp_frames(
+    start_bytes: &[u8],
+    end_bytes: &[u8],
+    frames_bytes: &[u8],
+) -> Result<Vec<u8>, String> {
+    let start: RMatrix = Matrix::from_bytes(start_bytes)?;
+    let end: RMatrix = Matrix::from_bytes(end_bytes)?;
+    let frames = usize::from_bytes(frames_bytes)?;
+    let (q1, q2) = (
+        Quaternion::from_rotation_matrix(&start)?,
+        Quaternion::from_rotation_matrix(&end)?,
+    );
+    let mut res = Vec::with_capacity(frames);
+    for k in 0..frames {
+        let t = if frames == 1 {
+            0.0
+        } else {
+            k as f64 / (frames - 1) as f64
+        };
+        let q = q1.slerp(&q2, t)?;
+        let mat = if start.nrows() == 4 {
+            q.to_homogeneous_matrix()?
+        } else {
+            q.to_rotation_matrix()?
+        };
+        res.push(mat.to_bytes());
+    }
+    Ok(res.join(&b'|'))
+}
+
+builder!(
+    rotation_2d_about,
+    { |angle, cx, cy| RMatrix::rotation_2d_about(angle, cx, cy) },
+    angle,
+    cx,
+    cy
+);
+builder!(
+    scaling_2d_about,
+    { |sx, sy, cx, cy| RMatrix::scaling_2d_about(sx, sy, cx, cy) },
+    sx,
+    sy,
+    cx,
+    cy
+);
+builder!(
+    reflection_2d,
+    { |angle, px, py| RMatrix::reflection_2d(angle, px, py) },
+    angle,
+    px,
+    py
+);
+builder!(shear_2d, { |kx, ky| RMatrix::shear_2d(kx, ky) }, kx, ky);
+
+/// Takes matrices separated by `|`, applied from first to last.
+#[wasm_func]
+pub fn compose(mats_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mats = mats_bytes
+        .split(|b| *b == b'|')
+        .map(RMatrix::from_bytes)
+        .collect::<Result<Vec<_>, _>>()?;
+    let res = RMatrix::compose(&mats)?;
+    Ok(res.to_bytes())
+}
+
+#[wasm_func]
+pub fn transform_points_2d(mat_bytes: &[u8], points_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    let points: RMatrix = Matrix::from_bytes(points_bytes)?;
+    let res = mat.transform_points_2d(&points)?;
+    Ok(res.to_bytes())
+}
+
+/// Returns the transform, then `|`, then the residual of every point pair.
+fn estimate_output((transform, residuals): (RMatrix, Vec<f64>)) -> Vec<u8> {
+    let mut res = transform.to_bytes();
+    res.push(b'|');
+    res.extend(Vector::from(residuals).to_bytes());
+    res
+}
+
+#[wasm_func]
+pub fn estimate_affine(source_bytes: &[u8], target_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let source: RMatrix = Matrix::from_bytes(source_bytes)?;
+    let target: RMatrix = Matrix::from_bytes(target_bytes)?;
+    let res = RMatrix::estimate_affine(&source, &target)?;
+    Ok(estimate_output(res))
+}
+
+#[wasm_func]
+pub fn estimate_homography(source_bytes: &[u8], target_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let source: RMatrix = Matrix::from_bytes(source_bytes)?;
+    let target: RMatrix = Matrix::from_bytes(target_bytes)?;
+    let res = RMatrix::estimate_homography(&source, &target)?;
+    Ok(estimate_output(res))
+}
+
+/// Returns the rotation, translation and `scale,rmsd`, separated by `|`.
+#[wasm_func]
+pub fn kabsch(
+    source_bytes: &[u8],
+    target_bytes: &[u8],
+    scale_bytes: &[u8],
+) -> Result<Vec<u8>, String> {
+    let source: RMatrix = Matrix::from_bytes(source_bytes)?;
+    let target: RMatrix = Matrix::from_bytes(target_bytes)?;
+    let with_scale = bool::from_bytes(scale_bytes)?;
+    let res = RMatrix::kabsch(&source, &target, with_scale)?;
+    Ok([
+        res.rotation.to_bytes(),
+        res.translation.to_bytes(),
+        Vector::from(vec![res.scale, res.rmsd]).to_bytes(),
+    ]
+    .join(&b'|'))
+}
+
+#[wasm_func]
+pub fn transform_points(
+    mat_bytes: &[u8],
+    points_bytes: &[u8],
+    near_bytes: &[u8],
+    viewport_bytes: &[u8],
+) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    let points: RMatrix = Matrix::from_bytes(points_bytes)?;
+    let near = f64::from_bytes(near_bytes)?;
+    let viewport = if viewport_bytes.is_empty() {
+        None
+    } else {
+        match Vector::<f64>::from_bytes(viewport_bytes)?.entries[..] {
+            [width, height] => Some((width, height)),
+            _ => return Err("Viewport must be given as width,height".to_owned()),
+        }
+    };
+    let res = mat.transform_points(&points, near, viewport)?;
+    Ok(res.to_bytes())
+}
+
+fn gram_schmidt_rows<T: Scalar>(
+    mat: Matrix<T>,
+    method: GramSchmidt,
+) -> Result<Vec<u8>, LinalgError> {
+    let vectors: Vec<Vector<T>> = mat.rows().map(|row| Vector::from(row.to_vec())).collect();
+    let basis = orthogonal::gram_schmidt(&vectors, method)?;
+    let res = Matrix::from_rows(basis.into_iter().map(Vec::from).collect())?;
+    Ok(res.to_bytes())
+}
+
+/// Orthonormalises the rows of a matrix. As in `render`, a matrix with
+/// fractions such as `1/2` is orthogonalised exactly, without normalising,
+/// if all its entries are exact, and complex entries such as `1+2i` are
+/// accepted too.
+#[wasm_func]
+pub fn gram_schmidt(mat_bytes: &[u8], method_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let method: GramSchmidt = std::str::from_utf8(method_bytes)
+        .map_err(LinalgError::from)?
+        .parse()?;
+    let exact = if mat_bytes.contains(&b'/') {
+        Matrix::<Fraction>::from_bytes(mat_bytes).ok()
+    } else {
+        None
+    };
+    let res = if let Some(mat) = exact {
+        gram_schmidt_rows(mat, method)?
+    } else if let Ok(mat) = RMatrix::from_bytes(mat_bytes) {
+        gram_schmidt_rows(mat, method)?
+    } else {
+        gram_schmidt_rows(Matrix::<Complex64>::from_bytes(mat_bytes)?, method)?
+    };
+    Ok(res)
+}
+
+/// Returns the elimination steps as JSON, see `trace::steps_to_json`. The
+/// entries are parsed as fractions, so every step is exact.
+#[wasm_func]
+pub fn elimination_steps(mat_bytes: &[u8], reduced_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: Matrix<Fraction> = Matrix::from_bytes(mat_bytes)?;
+    let steps = if bool::from_bytes(reduced_bytes)? {
+        mat.reduced_echelon_traced()
+    } else {
+        mat.echelon_traced()
+    };
+    Ok(trace::steps_to_json(&mat, &steps).into_bytes())
+}
+
+/// Returns the expansion tree as JSON, see `Expansion::to_json`. `line_bytes`
+/// is `row,i`, `col,j` or empty to pick the line with the most zeros.
+#[wasm_func]
+pub fn det_laplace(mat_bytes: &[u8], line_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: Matrix<Fraction> = Matrix::from_bytes(mat_bytes)?;
+    let line = if line_bytes.is_empty() {
+        None
+    } else {
+        let line_str = std::str::from_utf8(line_bytes).map_err(LinalgError::from)?;
+        Some(line_str.parse::<laplace::Line>()?)
+    };
+    Ok(mat.det_laplace(line)?.to_json().into_bytes())
+}
+
+/// Rewrites a matrix in the format given by `format_bytes`, see
+/// `NumberFormat`. Other results are written in full precision so that they
+/// can be passed on, and the Typst wrappers show them through this function
+/// in the default format.
+#[wasm_func]
+pub fn format_matrix(mat_bytes: &[u8], format_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    let format: NumberFormat = std::str::from_utf8(format_bytes)
+        .map_err(LinalgError::from)?
+        .parse()?;
+    Ok(mat.to_bytes_with(&format))
+}
+
+/// Rewrites a matrix with every entry in its exact form where one is found,
+/// such as `1/3` or `√2/2`; see `Recovery` for the options.
+#[wasm_func]
+pub fn exact(mat_bytes: &[u8], options_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    let recovery: Recovery = std::str::from_utf8(options_bytes)
+        .map_err(LinalgError::from)?
+        .parse()?;
+    let res = mat
+        .to_exact_forms(&recovery)
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|form| form.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect::<Vec<String>>()
+        .join(";");
+    Ok(res.into_bytes())
+}
+
+/// Renders a matrix as Typst or LaTeX markup, see `RenderOptions`. Entries
+/// may be decimals, fractions such as `1/2` or complex numbers such as `1+2i`.
+/// A matrix with fractions is rendered exactly if all its entries are exact.
+#[wasm_func]
+pub fn render(mat_bytes: &[u8], options_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let options: RenderOptions = std::str::from_utf8(options_bytes)
+        .map_err(LinalgError::from)?
+        .parse()?;
+    let exact = if mat_bytes.contains(&b'/') {
+        Matrix::<Fraction>::from_bytes(mat_bytes).ok()
+    } else {
+        None
+    };
+    let markup = if let Some(mat) = exact {
+        mat.to_markup(&options)?
+    } else if let Ok(mat) = RMatrix::from_bytes(mat_bytes) {
+        mat.to_markup(&options)?
+    } else {
+        Matrix::<Complex64>::from_bytes(mat_bytes)?.to_markup(&options)?
+    };
+    Ok(markup.into_bytes())
+}
+
+#[wasm_func]
+pub fn format_vector(vec_bytes: &[u8], format_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let vec: Vector<f64> = Vector::from_bytes(vec_bytes)?;
+    let format: NumberFormat = std::str::from_utf8(format_bytes)
+        .map_err(LinalgError::from)?
+        .parse()?;
+    Ok(vec.to_bytes_with(&format))
+}
+
+#[wasm_func]
+pub fn slice(mat_bytes: &[u8], rows_bytes: &[u8], cols_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    let rows = Range::<usize>::from_bytes(rows_bytes)?;
+    let cols = Range::<usize>::from_bytes(cols_bytes)?;
+    let res = mat.view(rows, cols)?.to_matrix();
+    Ok(res.to_bytes())
+}
+
+type RSparse = CsrMatrix<f64>;
+
+#[wasm_func]
+pub fn sparse_to_dense(sparse_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let sparse = RSparse::from_bytes(sparse_bytes)?;
+    Ok(sparse.to_dense().to_bytes())
+}
+
+#[wasm_func]
+pub fn dense_to_sparse(mat_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    Ok(RSparse::from_dense(&mat).to_bytes())
+}
+
+#[wasm_func]
+pub fn sparse_transpose(sparse_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let sparse = RSparse::from_bytes(sparse_bytes)?;
+    Ok(sparse.transpose().to_bytes())
+}
+
+#[wasm_func]
+pub fn sparse_mul(sparse_bytes1: &[u8], sparse_bytes2: &[u8]) -> Result<Vec<u8>, String> {
+    let sparse1 = RSparse::from_bytes(sparse_bytes1)?;
+    let sparse2 = RSparse::from_bytes(sparse_bytes2)?;
+    let res = sparse1.mul_sparse(&sparse2)?;
+    Ok(res.to_bytes())
+}
+
+#[wasm_func]
+pub fn sparse_mul_dense(sparse_bytes: &[u8], mat_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let sparse = RSparse::from_bytes(sparse_bytes)?;
+    let mat: RMatrix = Matrix::from_bytes(mat_bytes)?;
+    let res = sparse.mul_dense(&mat)?;
+    Ok(res.to_bytes())
+}
+
+#[wasm_func]
+pub fn sparse_mul_vec(sparse_bytes: &[u8], vec_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let sparse = RSparse::from_bytes(sparse_bytes)?;
+    let vec = Vector::from_bytes(vec_bytes)?;
+    let res = sparse.mul_vector(&vec)?;
+    Ok(res.to_bytes())
+}
+
+#[wasm_func]
+pub fn sparse_solve(
+    sparse_bytes: &[u8],
+    vec_bytes: &[u8],
+    tol_bytes: &[u8],
+) -> Result<Vec<u8>, String> {
+    let sparse = RSparse::from_bytes(sparse_bytes)?;
+    let vec = Vector::from_bytes(vec_bytes)?;
+    let tol = f64::from_bytes(tol_bytes)?;
+    let res = sparse.solve_cg(&vec, tol, 10 * sparse.nrows().max(10))?;
+    Ok(res.to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &RMatrix, b: &RMatrix) {
+        let diff = a.checked_sub(b).unwrap();
+        let scale = tolerance::frobenius_norm(b.as_slice());
+        assert!(tolerance::frobenius_norm(diff.as_slice()) <= 1e-12 * scale);
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_diagonal() -> Result<(), String> {
+        let mat = b"4,7,2;3,6,1;2,5,3";
+        let product = mul(&inverse(mat, b"")?, mat)?;
+        assert_eq!(is_diagonal(&product, b"")?, b"true");
+        Ok(())
+    }
+
+    #[test]
+    fn small_matrices_match_the_general_path() -> Result<(), String> {
+        let mat = b"4,7,2;3,6,1;2,5,3";
+        let general: RMatrix = Matrix::from_bytes(mat)?;
+        let det = f64::from_bytes(&det(mat)?)?;
+        assert!((det - general.det()?).abs() < 1e-12);
+        let inv = RMatrix::from_bytes(&inverse(mat, b"")?)?;
+        assert_close(&inv, &general.inverse()?);
+        assert_eq!(pow(mat, b"-1")?, inverse(mat, b"")?);
+        let cube = RMatrix::from_bytes(&pow(mat, b"3")?)?;
+        assert_close(&cube, &general.powi(3)?);
+        assert!(inverse(b"1,2;2,4", b"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn results_pass_on_full_precision_and_show_rounded() -> Result<(), String> {
+        let inv = inverse(b"4,7,2;3,6,1;2,5,3", b"")?;
+        assert!(inv.starts_with(b"1.4444444444444442,"));
+        let shown = format_matrix(&inv, b"")?;
+        assert_eq!(
+            shown,
+            b"1.44444444444,-1.22222222222,-0.555555555556;\
+              -0.777777777778,0.888888888889,0.222222222222;\
+              0.333333333333,-0.666666666667,0.333333333333"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn format_keeps_small_entries_of_large_matrices() -> Result<(), String> {
+        let res = format_matrix(b"1e12,0.25;3.7,1", b"sig:12,snap:1e-12")?;
+        assert_eq!(res, b"1000000000000,0.25;3.7,1");
+        let res = format_matrix(b"1e-13,2e-13;-1e-30,1.0000000000000002e-13", b"")?;
+        assert_eq!(res, b"1e-13,2e-13;0,1e-13");
+        let res = format_matrix(b"-0.0001,2;1e6,0.5", b"dec:2")?;
+        assert_eq!(res, b"0.00,2.00;1000000.00,0.50");
+        let markup = String::from_utf8(render(b"1e12,0.25;3.7,1", b"typst")?).unwrap();
+        assert_eq!(markup, "mat(delim: \"[\", 1000000000000, 0.25; 3.7, 1)");
+        Ok(())
+    }
+
+    #[test]
+    fn render_keeps_fractions() -> Result<(), String> {
+        let markup = String::from_utf8(render(b"1/2,1;0,1/3", b"typst")?).unwrap();
+        assert_eq!(markup, "mat(delim: \"[\", frac(1, 2), 1; 0, frac(1, 3))");
+        Ok(())
+    }
+
+    #[test]
+    fn projections_reject_degenerate_volumes() {
+        assert!(perspective(b"1", b"1", b"0", b"100").is_err());
+        assert!(perspective(b"0", b"1", b"1", b"100").is_err());
+        assert!(perspective(b"3.2", b"1", b"1", b"100").is_err());
+        assert!(perspective(b"1", b"1", b"1", b"1").is_err());
+        assert!(frustum(b"1", b"1", b"-1", b"1", b"1", b"10").is_err());
+        assert!(orthographic(b"-1", b"1", b"2", b"2", b"1", b"10").is_err());
+        assert!(orthographic(b"-1", b"1", b"-1", b"1", b"-1", b"1").is_ok());
+        assert!(perspective(b"1", b"1.5", b"0.1", b"100").is_ok());
+    }
+
+    #[test]
+    fn gram_schmidt_picks_the_scalar_type() -> Result<(), String> {
+        let exact = gram_schmidt(b"1,1/2;1,0", b"modified")?;
+        assert_eq!(exact, b"1,1/2;1/5,-2/5");
+        let float = RMatrix::from_bytes(&gram_schmidt(b"3,4;1,0", b"modified")?)?;
+        assert_close(
+            &float,
+            &Matrix::from_rows(vec![vec![0.6, 0.8], vec![0.8, -0.6]])?,
+        );
+        let complex = gram_schmidt(b"1i,0;1,1", b"classical")?;
+        assert!(Matrix::<Complex64>::from_bytes(&complex).is_ok());
+        assert!(gram_schmidt(b"1,2;1/2,1", b"modified").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn sparse_solve_rejects_indefinite_matrices() -> Result<(), String> {
+        let indefinite = b"2,2;0,0,1;0,1,2;1,0,2;1,1,1";
+        assert!(sparse_solve(indefinite, b"1,0", b"1e-10").is_err());
+        let definite = b"2,2;0,0,2;0,1,1;1,0,1;1,1,2";
+        let x = Vector::<f64>::from_bytes(&sparse_solve(definite, b"3,3", b"1e-12")?)?;
+        assert!((x[0] - 1.0).abs() < 1e-9 && (x[1] - 1.0).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn det_laplace_rejects_large_matrices() {
+        let row = ["1"; 9].join(",");
+        let mat = vec![row; 9].join(";");
+        let err = det_laplace(mat.as_bytes(), b"").unwrap_err();
+        assert!(err.contains("8×8"));
+        assert!(det_laplace(b"1,2,0;0,1,3;4,0,1", b"").is_ok());
+    }
+
+    #[test]
+    fn transform_points_flips_y_in_the_viewport() -> Result<(), String> {
+        let id = b"1,0,0,0;0,1,0,0;0,0,1,0;0,0,0,1";
+        let res = RMatrix::from_bytes(&transform_points(
+            id,
+            b"-1,1,0;1,-1,0",
+            b"1e-6",
+            b"200,100",
+        )?)?;
+        assert_eq!(res[0][..2], [0.0, 0.0]);
+        assert_eq!(res[1][..2], [200.0, 100.0]);
+        assert!(transform_points(id, b"0,0,0", b"0", b"").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use std::str::FromStr;
+
+use fraction::Fraction;
+use num::complex::{c64, Complex64};
+use num_traits::{CheckedDiv, CheckedMul};
+
+use crate::common::*;
+use crate::error::LinalgError;
+
+/// A real number as written, with its exact value if it fits in a
+/// `Fraction`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Real {
+    pub value: f64,
+    pub exact: Option<Fraction>,
+}
+
+impl Real {
+    fn zero() -> Self {
+        Real {
+            value: 0.0,
+            exact: Some(Fraction::from(0)),
+        }
+    }
+
+    fn one() -> Self {
+        Real {
+            value: 1.0,
+            exact: Some(Fraction::from(1)),
+        }
+    }
+
+    fn neg(self) -> Self {
+        Real {
+            value: -self.value,
+            exact: self.exact.map(|x| -x),
+        }
+    }
+}
+
+/// A number as it may be written in a matrix entry: `a`, `b i` or `a + b i`,
+/// where `a` and `b` are decimals, possibly in scientific notation, or
+/// fractions of them. `j` may stand for `i`, `−` (U+2212) for `-`, and
+/// whitespace may separate the parts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Literal {
+    pub re: Real,
+    pub im: Real,
+}
+
+/// A recursive descent parser over the characters of one entry.
+struct Lexer<'a> {
+    source: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Lexer {
+            source,
+            chars: source.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self) -> LinalgError {
+        let message = match self.peek() {
+            Some(c) => format!(
+                "Unexpected '{}' at character {} of '{}'",
+                c,
+                self.pos + 1,
+                self.source.trim()
+            ),
+            None if self.source.trim().is_empty() => "Missing number".to_owned(),
+            None => format!("Unexpected end of '{}'", self.source.trim()),
+        };
+        LinalgError::parse(message, None)
+    }
+
+    /// Consumes a `+` or `-` and returns whether it was a minus.
+    fn sign(&mut self) -> Option<bool> {
+        self.skip_whitespace();
+        let negative = match self.peek()? {
+            '+' => false,
+            '-' | '\u{2212}' => true,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(negative)
+    }
+
+    fn digits(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// An unsigned decimal such as `12`, `.5`, `1.25e-3` or `4E2`.
+    fn decimal(&mut self) -> Result<Real, LinalgError> {
+        let integer = self.digits();
+        let fractional = if self.peek() == Some('.') {
+            self.pos += 1;
+            self.digits()
+        } else {
+            String::new()
+        };
+        if integer.is_empty() && fractional.is_empty() {
+            return Err(self.error());
+        }
+        let mut exponent = 0i32;
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.pos += 1;
+            let negative = matches!(self.peek(), Some('-' | '\u{2212}'));
+            if matches!(self.peek(), Some('+' | '-' | '\u{2212}')) {
+                self.pos += 1;
+            }
+            let digits = self.digits();
+            exponent = digits.parse().map_err(|_| self.error())?;
+            if negative {
+                exponent = -exponent;
+            }
+        }
+
+        let mantissa = format!(
+            "{}.{}",
+            if integer.is_empty() { "0" } else { &integer },
+            if fractional.is_empty() {
+                "0"
+            } else {
+                &fractional
+            }
+        );
+        let value = format!("{}e{}", mantissa, exponent)
+            .parse()
+            .map_err(|_| self.error())?;
+        let exact = Fraction::from_str(&mantissa).ok().and_then(|m| {
+            let power = Fraction::from(10u64.checked_pow(exponent.unsigned_abs())?);
+            if exponent >= 0 {
+                m.checked_mul(&power)
+            } else {
+                m.checked_div(&power)
+            }
+        });
+        Ok(Real { value, exact })
+    }
+
+    /// A decimal, or a fraction `p/q` of decimals.
+    fn real(&mut self) -> Result<Real, LinalgError> {
+        let numer = self.decimal()?;
+        self.skip_whitespace();
+        if self.peek() != Some('/') {
+            return Ok(numer);
+        }
+        self.pos += 1;
+        self.skip_whitespace();
+        let denom = self.decimal()?;
+        if denom.value == 0.0 {
+            return Err(LinalgError::parse(
+                format!("Division by zero in '{}'", self.source.trim()),
+                None,
+            ));
+        }
+        let exact = match (numer.exact, denom.exact) {
+            (Some(n), Some(d)) => n.checked_div(&d),
+            _ => None,
+        };
+        Ok(Real {
+            value: numer.value / denom.value,
+            exact,
+        })
+    }
+
+    /// A signed real, optionally followed by `i`, or a bare signed `i`.
+    /// Returns the real and whether it is imaginary.
+    fn term(&mut self, negative: bool) -> Result<(Real, bool), LinalgError> {
+        self.skip_whitespace();
+        let real = if matches!(self.peek(), Some('i' | 'j')) {
+            Real::one()
+        } else {
+            self.real()?
+        };
+        self.skip_whitespace();
+        let imaginary = matches!(self.peek(), Some('i' | 'j'));
+        if imaginary {
+            self.pos += 1;
+        }
+        Ok((if negative { real.neg() } else { real }, imaginary))
+    }
+
+    fn literal(&mut self) -> Result<Literal, LinalgError> {
+        let negative = self.sign().unwrap_or(false);
+        let (first, first_imaginary) = self.term(negative)?;
+        let second = match self.sign() {
+            Some(negative) => Some(self.term(negative)?),
+            None => None,
+        };
+        self.skip_whitespace();
+        if self.peek().is_some() {
+            return Err(self.error());
+        }
+        match (first_imaginary, second) {
+            (false, None) => Ok(Literal {
+                re: first,
+                im: Real::zero(),
+            }),
+            (true, None) => Ok(Literal {
+                re: Real::zero(),
+                im: first,
+            }),
+            (false, Some((second, true))) => Ok(Literal {
+                re: first,
+                im: second,
+            }),
+            (true, Some((second, false))) => Ok(Literal {
+                re: second,
+                im: first,
+            }),
+            _ => Err(LinalgError::parse(
+                format!(
+                    "Expected one real and one imaginary part in '{}'",
+                    self.source.trim()
+                ),
+                None,
+            )),
+        }
+    }
+}
+
+impl FromStr for Literal {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Lexer::new(s).literal()
+    }
+}
+
+/// Scalars that can be built from a parsed `Literal`.
+pub trait FromLiteral: Scalar {
+    fn from_literal(literal: &Literal) -> Result<Self, String>;
+}
+
+fn real_part(literal: &Literal) -> Result<&Real, String> {
+    if literal.im.value != 0.0 {
+        return Err("Complex number where a real one is needed".to_owned());
+    }
+    Ok(&literal.re)
+}
+
+/// Other scalars, such as integers, parse the exact value as written by
+/// `Fraction`, so that for example `4/2` is read as `2`.
+impl<T: Scalar> FromLiteral for T {
+    default fn from_literal(literal: &Literal) -> Result<T, String> {
+        let re = real_part(literal)?;
+        re.exact
+            .as_ref()
+            .and_then(|x| x.to_string().parse().ok())
+            .or_else(|| re.value.to_string().parse().ok())
+            .ok_or_else(|| "Number cannot be represented by the entry type".to_owned())
+    }
+}
+
+impl FromLiteral for f64 {
+    fn from_literal(literal: &Literal) -> Result<f64, String> {
+        Ok(real_part(literal)?.value)
+    }
+}
+
+impl FromLiteral for Fraction {
+    fn from_literal(literal: &Literal) -> Result<Fraction, String> {
+        real_part(literal)?
+            .exact
+            .ok_or_else(|| "Number is too large or too precise to be exact".to_owned())
+    }
+}
+
+impl FromLiteral for Complex64 {
+    fn from_literal(literal: &Literal) -> Result<Complex64, String> {
+        Ok(c64(literal.re.value, literal.im.value))
+    }
+}
+
+/// Parses `token` as a scalar of type `T`.
+pub fn parse_scalar<T: Scalar>(token: &str) -> Result<T, LinalgError> {
+    let literal: Literal = token.parse()?;
+    T::from_literal(&literal)
+        .map_err(|message| LinalgError::parse(format!("{} in '{}'", message, token.trim()), None))
+}
@@ -1,65 +1,93 @@
 #![allow(dead_code)]
 #![feature(min_specialization)]
 use fraction::Fraction;
-use num::complex::c64;
 
+mod align;
+mod cli;
 mod common;
 mod convert;
+mod error;
+mod estimate;
+mod format;
+mod kernel;
+mod laplace;
+mod literal;
+mod markup;
 mod matrix;
+mod multiply;
+mod orthogonal;
+mod quaternion;
+mod rational;
+mod repl;
+mod rotation;
+mod smatrix;
+mod sparse;
+mod tolerance;
+mod trace;
+mod transform;
 mod vector;
+mod view;
 
 use matrix::*;
-use vector::*;
+use multiply::*;
+use std::time::{Duration, Instant};
 type F = Fraction;
 
-#[allow(unused)]
-fn main() {
-    let m1 = Matrix {
-        rows: vec![
-            vec![1.0, -1.0, 4.0],
-            vec![1.0, 4.0, -2.0],
-            vec![1.0, 4.0, 2.0],
-            vec![1.0, -1.0, 0.0],
-        ],
-    };
-    let m2 = Matrix {
-        rows: vec![
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-            vec![10.0, 11.0, 12.0],
-        ],
-    };
-    let mut id: Matrix<f64> = Matrix::id(15);
-    let a = Matrix::filled(12, 14, 1.0);
-    let v1 = Vector {
-        entries: vec![1.0, 0.0],
-    };
-    let v2 = Vector {
-        entries: vec![0.0, 1.0],
-    };
-    println!("Angle is {}", v1.angle_with(&v2).unwrap());
+fn time<R>(f: impl Fn() -> R) -> Duration {
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs < 3 || start.elapsed() < Duration::from_millis(200) {
+        std::hint::black_box(f());
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
 
-    let n = Matrix {
-        rows: vec![
-            vec![c64(1.0, 2.0), c64(-4.0, 1.0)],
-            vec![c64(0.1, 5.0), c64(3.0, 1.2)],
-            vec![c64(1.0, 2.0), c64(-4.0, 1.0)],
-            vec![c64(0.1, 5.0), c64(3.0, 1.2)],
-        ],
-    };
+/// Times the multiplication kernels against each other, to find where the
+/// blocked and Strassen–Winograd paths start paying off.
+fn bench_multiplication() {
+    println!("f64: naive vs cache-blocked");
+    for dim in [64, 128, 256, 512, 768, 1024] {
+        let a = Matrix::from_vec(
+            dim,
+            dim,
+            (0..dim * dim).map(|x| (x % 17) as f64 - 8.0).collect(),
+        )
+        .unwrap();
+        let naive = time(|| mul_naive(&a, &a));
+        let blocked = time(|| mul_blocked(&a, &a));
+        println!("{:>4}: {:>12?} {:>12?}", dim, naive, blocked);
+    }
 
-    let v = Matrix::vandermonde(&[F::from(2), F::new(1u64, 2u64)], 4);
-    println!("Vandermonde\n{}", v);
-    let u = Matrix {
-        rows: vec![vec![0.3, 0.0], vec![0.2, 0.1]],
-    };
-    println!("{}", u.is_lower_triangular())
+    println!("Fraction: naive vs Strassen-Winograd");
+    for dim in [32, 64, 96, 128, 192] {
+        let a = Matrix::from_vec(
+            dim,
+            dim,
+            (0..dim * dim)
+                .map(|x| F::from(x % 7) - F::new(3u64, 2u64))
+                .collect(),
+        )
+        .unwrap();
+        let naive = time(|| mul_naive(&a, &a));
+        let strassen = time(|| mul_strassen(&a, &a));
+        println!("{:>4}: {:>12?} {:>12?}", dim, naive, strassen);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("bench") => return bench_multiplication(),
+        Some("repl") => return repl::run(),
+        None | Some("-h" | "--help") => return println!("{}", cli::USAGE),
+        _ => {}
+    }
+    match cli::Command::parse(&args).and_then(|command| command.run()) {
+        Ok(output) => println!("{}", output),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,209 @@
+use std::str::FromStr;
+
+use fraction::Fraction;
+use num::complex::Complex64;
+
+use crate::common::*;
+use crate::error::LinalgError;
+use crate::format::NumberFormat;
+use crate::matrix::Matrix;
+use crate::tolerance::{frobenius_norm, Approximate, Tolerance};
+
+/// The markup language a matrix is rendered in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Markup {
+    Typst,
+    Latex,
+}
+
+/// How a matrix is rendered: in which language, with a vertical line before
+/// column `augment` if given, and with boxed pivots, the first nonzero entry
+/// of every row, if `pivots` is set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    pub markup: Markup,
+    pub augment: Option<usize>,
+    pub pivots: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            markup: Markup::Typst,
+            augment: None,
+            pivots: false,
+        }
+    }
+}
+
+/// Parses comma separated options: `typst` or `latex`, `augment:n` and
+/// `pivots`. The empty string gives the default, plain Typst.
+impl FromStr for RenderOptions {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut options = RenderOptions::default();
+        for option in s.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            let err = || LinalgError::parse(format!("Invalid render option '{}'", option), None);
+            match option.split_once(':') {
+                None if option == "typst" => options.markup = Markup::Typst,
+                None if option == "latex" => options.markup = Markup::Latex,
+                None if option == "pivots" => options.pivots = true,
+                Some(("augment", col)) => {
+                    options.augment = Some(col.trim().parse().map_err(|_| err())?)
+                }
+                _ => return Err(err()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Scalars that can be written as math markup. Integers and other exact
+/// scalars are written as they display.
+pub trait MarkupScalar: Scalar {
+    /// Writes an entry of a matrix of norm `scale`, which sets how close to
+    /// an integer floating point entries are snapped to it.
+    fn to_markup_scaled(&self, markup: Markup, scale: f64) -> String;
+
+    /// Writes a scalar on its own.
+    fn to_markup(&self, markup: Markup) -> String {
+        self.to_markup_scaled(markup, self.magnitude())
+    }
+}
+
+impl<T: Scalar> MarkupScalar for T {
+    default fn to_markup_scaled(&self, _markup: Markup, _scale: f64) -> String {
+        self.to_string()
+    }
+}
+
+/// Written in the default `NumberFormat`, with scientific notation as a
+/// power of ten.
+impl MarkupScalar for f64 {
+    fn to_markup_scaled(&self, markup: Markup, scale: f64) -> String {
+        let s = NumberFormat::default().format_scaled(*self, scale);
+        let Some((mantissa, exponent)) = s.split_once('e') else {
+            return s;
+        };
+        match markup {
+            Markup::Typst => format!("{} dot 10^({})", mantissa, exponent),
+            Markup::Latex => format!("{} \\cdot 10^{{{}}}", mantissa, exponent),
+        }
+    }
+}
+
+impl MarkupScalar for Fraction {
+    fn to_markup_scaled(&self, markup: Markup, _scale: f64) -> String {
+        let sign = if self.is_sign_negative() { "-" } else { "" };
+        let (Some(numer), Some(denom)) = (self.numer(), self.denom()) else {
+            return self.to_string();
+        };
+        match (markup, denom) {
+            (_, 1) => format!("{}{}", sign, numer),
+            (Markup::Typst, _) => format!("{}frac({}, {})", sign, numer, denom),
+            (Markup::Latex, _) => format!("{}\\frac{{{}}}{{{}}}", sign, numer, denom),
+        }
+    }
+}
+
+/// Written as `a + b i`, leaving out a zero part and a unit coefficient.
+impl MarkupScalar for Complex64 {
+    fn to_markup_scaled(&self, markup: Markup, scale: f64) -> String {
+        let re = self.re.to_markup_scaled(markup, scale);
+        let abs_im = self.im.abs().to_markup_scaled(markup, scale);
+        let im = match abs_im.as_str() {
+            "1" => "i".to_owned(),
+            _ => format!("{} i", abs_im),
+        };
+        let sign = if self.im < 0.0 { "-" } else { "+" };
+        match (re == "0", abs_im == "0") {
+            (_, true) => re,
+            (true, false) if self.im < 0.0 => format!("-{}", im),
+            (true, false) => im,
+            (false, false) => format!("{} {} {}", re, sign, im),
+        }
+    }
+}
+
+fn boxed(entry: String, markup: Markup) -> String {
+    match markup {
+        Markup::Typst => format!("#box(stroke: 0.5pt, inset: 2pt, ${}$)", entry),
+        Markup::Latex => format!("\\boxed{{{}}}", entry),
+    }
+}
+
+impl<T: Scalar> Matrix<T> {
+    /// The column of the first nonzero entry of every row, up to the default
+    /// tolerance for floating point entries.
+    fn leading_entries(&self) -> Vec<Option<usize>> {
+        let threshold = Tolerance::default().threshold(frobenius_norm(self.as_slice()));
+        self.rows()
+            .map(|row| row.iter().position(|x| !x.is_negligible(threshold)))
+            .collect()
+    }
+
+    /// Renders the matrix as Typst math, `mat(delim: "[", frac(1, 2), 0; 0, 1)`,
+    /// or as a LaTeX `bmatrix`, or an `array` if it is augmented. The result
+    /// can be evaluated in math mode or pasted into a formula.
+    pub fn to_markup(&self, options: &RenderOptions) -> Result<String, LinalgError> {
+        if let Some(col) = options.augment {
+            if col == 0 || col >= self.ncols() {
+                return Err(LinalgError::out_of_bounds(
+                    "Augment column",
+                    col,
+                    self.ncols(),
+                ));
+            }
+        }
+        let scale = frobenius_norm(self.as_slice());
+        let leading = if options.pivots {
+            self.leading_entries()
+        } else {
+            vec![None; self.nrows()]
+        };
+        let rows: Vec<Vec<String>> = self
+            .rows()
+            .zip(leading)
+            .map(|(row, pivot)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, x)| {
+                        let entry = x.to_markup_scaled(options.markup, scale);
+                        if pivot == Some(j) {
+                            boxed(entry, options.markup)
+                        } else {
+                            entry
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(match options.markup {
+            Markup::Typst => {
+                let body: Vec<String> = rows.iter().map(|row| row.join(", ")).collect();
+                let augment = match options.augment {
+                    Some(col) => format!("augment: #{}, ", col),
+                    None => String::new(),
+                };
+                format!("mat(delim: \"[\", {}{})", augment, body.join("; "))
+            }
+            Markup::Latex => {
+                let body: Vec<String> = rows.iter().map(|row| row.join(" & ")).collect();
+                match options.augment {
+                    Some(col) => format!(
+                        "\\left[\\begin{{array}}{{{}|{}}} {} \\end{{array}}\\right]",
+                        "c".repeat(col),
+                        "c".repeat(self.ncols() - col),
+                        body.join(" \\\\ ")
+                    ),
+                    None => format!(
+                        "\\begin{{bmatrix}} {} \\end{{bmatrix}}",
+                        body.join(" \\\\ ")
+                    ),
+                }
+            }
+        })
+    }
+}
@@ -1,7 +1,12 @@
 use core::fmt;
 
 use crate::common::*;
-use crate::convert::Convertable;
+use crate::convert::{parse_entry, Convertable};
+use crate::error::{LinalgError, Position};
+use crate::format::{FormatScalar, NumberFormat};
+use crate::kernel::Kernel;
+use crate::multiply::MatMul;
+use crate::tolerance::{frobenius_norm, Approximate, Tolerance};
 use crate::vector::Vector;
 use num::complex::{c64, Complex64, ComplexFloat};
 
@@ -10,38 +15,39 @@ pub struct Matrix<T>
 where
     T: Scalar,
 {
-    pub rows: Vec<Vec<T>>,
+    nrows: usize,
+    ncols: usize,
+    data: Vec<T>,
 }
 
 pub trait Echelon: Sized {
-    fn echelon(&self) -> (Self, usize);
+    /// Row echelon form and the number of row swaps used. Entries within
+    /// `tol` of zero are never chosen as pivots, and are set to zero.
+    fn echelon_within(&self, tol: Tolerance) -> (Self, usize);
+
+    fn echelon(&self) -> (Self, usize) {
+        self.echelon_within(Tolerance::default())
+    }
 }
 
 impl<T: Scalar> std::fmt::Display for Matrix<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let strings: Vec<String> = self.data.iter().map(|x| x.to_string()).collect();
         let mut longest = vec![0; self.ncols()];
+        for (k, entry_str) in strings.iter().enumerate() {
+            let j = k % self.ncols();
+            longest[j] = longest[j].max(entry_str.len());
+        }
         let mut res = "".to_owned();
         for i in 0..self.nrows() {
             res.push('[');
             for j in 0..self.ncols() {
-                if longest[j] == 0 {
-                    longest[j] = self
-                        .get_column(j)
-                        .entries
-                        .to_owned()
-                        .iter()
-                        .map(|x| x.to_string().len())
-                        .max()
-                        .unwrap();
-                }
-                // println!("{:#?}", self.get_column(j).entries);
-                // println!("Longest at {} is {}", j, longest[j]);
-                let current_str = self[i][j].clone().to_string();
+                let current_str = &strings[i * self.ncols() + j];
                 res.push_str(&format!("{:^w$}", current_str, w = longest[j]));
                 if j == self.ncols() - 1 {
                     continue;
                 }
-                res.push_str(" ")
+                res.push(' ')
             }
             res.push_str("]\n");
         }
@@ -49,31 +55,35 @@ impl<T: Scalar> std::fmt::Display for Matrix<T> {
     }
 }
 
+/// Panics with the message of `err`, for the operators whose `checked_*`
+/// counterparts return it instead.
+fn expect_shape(result: Result<(), LinalgError>) {
+    if let Err(err) = result {
+        panic!("{}", err);
+    }
+}
+
+/// Panics if the shapes differ; see `checked_add` for a fallible version.
 impl<T: Scalar> std::ops::Add for Matrix<T> {
     type Output = Self;
     fn add(self, rhs: Self) -> Self::Output {
-        assert_eq!(self.ncols(), rhs.ncols());
-        assert_eq!(self.nrows(), rhs.nrows());
+        expect_shape(self.same_shape("add", &rhs));
         let mut out = self;
-        for i in 0..out.nrows() {
-            for j in 0..out.ncols() {
-                out[i][j] += rhs[i][j];
-            }
+        for (entry, other) in out.data.iter_mut().zip(rhs.data.iter()) {
+            *entry += *other;
         }
         out
     }
 }
 
+/// Panics if the shapes differ; see `checked_add` for a fallible version.
 impl<T: Scalar> std::ops::Add for &Matrix<T> {
     type Output = Matrix<T>;
     fn add(self, rhs: Self) -> Self::Output {
-        assert_eq!(self.ncols(), rhs.ncols());
-        assert_eq!(self.nrows(), rhs.nrows());
+        expect_shape(self.same_shape("add", rhs));
         let mut out = self.clone();
-        for i in 0..out.nrows() {
-            for j in 0..out.ncols() {
-                out[i][j] += rhs[i][j];
-            }
+        for (entry, other) in out.data.iter_mut().zip(rhs.data.iter()) {
+            *entry += *other;
         }
         out
     }
@@ -83,10 +93,8 @@ impl<T: Scalar> std::ops::Neg for Matrix<T> {
     type Output = Self;
     fn neg(self) -> Self::Output {
         let mut out = self;
-        for i in 0..out.nrows() {
-            for j in 0..out.ncols() {
-                out[i][j] = -out[i][j];
-            }
+        for entry in out.data.iter_mut() {
+            *entry = -*entry;
         }
         out
     }
@@ -95,108 +103,187 @@ impl<T: Scalar> std::ops::Neg for Matrix<T> {
 impl<T: Scalar> std::ops::Neg for &Matrix<T> {
     type Output = Matrix<T>;
     fn neg(self) -> Self::Output {
-        let mut out = self.clone();
-        for i in 0..self.nrows() {
-            for j in 0..self.ncols() {
-                out[i][j] *= -T::one();
-            }
-        }
-        out
+        -self.clone()
     }
 }
 
+/// Panics if the shapes differ; see `checked_sub` for a fallible version.
 impl<T: Scalar> std::ops::Sub for Matrix<T> {
     type Output = Self;
     fn sub(self, rhs: Self) -> Self::Output {
+        expect_shape(self.same_shape("subtract", &rhs));
         let mut out = self;
-        for i in 0..out.nrows() {
-            for j in 0..out.ncols() {
-                out[i][j] -= rhs[i][j];
-            }
+        for (entry, other) in out.data.iter_mut().zip(rhs.data.iter()) {
+            *entry -= *other;
         }
         out
     }
 }
 
+/// Panics if the shapes differ; see `checked_sub` for a fallible version.
 impl<T: Scalar> std::ops::Sub for &Matrix<T> {
     type Output = Matrix<T>;
     fn sub(self, rhs: Self) -> Self::Output {
+        expect_shape(self.same_shape("subtract", rhs));
         let mut out = self.clone();
-        for i in 0..out.nrows() {
-            for j in 0..out.ncols() {
-                out[i][j] -= rhs[i][j];
-            }
+        for (entry, other) in out.data.iter_mut().zip(rhs.data.iter()) {
+            *entry -= *other;
         }
         out
     }
 }
 
+/// Panics if the inner dimensions differ; see `checked_mul` for a fallible
+/// version.
 impl<T: Scalar> std::ops::Mul for Matrix<T> {
     type Output = Self;
     fn mul(self, rhs: Self) -> Self::Output {
-        assert_eq!(self.ncols(), rhs.nrows());
-        let mut out = Matrix::zero(self.nrows(), rhs.ncols());
-        for i in 0..self.nrows() {
-            for j in 0..rhs.ncols() {
-                for k in 0..rhs.nrows() {
-                    out[i][j] += self[i][k] * rhs[k][j];
-                }
-            }
-        }
-        out
+        &self * &rhs
     }
 }
 
+/// Panics if the inner dimensions differ; see `checked_mul` for a fallible
+/// version.
 impl<T: Scalar> std::ops::Mul for &Matrix<T> {
     type Output = Matrix<T>;
     fn mul(self, rhs: Self) -> Self::Output {
-        assert_eq!(self.ncols(), rhs.nrows());
-        let mut out = Matrix::zero(self.nrows(), rhs.ncols());
-        for i in 0..self.nrows() {
-            for j in 0..rhs.ncols() {
-                for k in 0..rhs.nrows() {
-                    out[i][j] += self[i][k] * rhs[k][j];
-                }
-            }
+        match self.checked_mul(rhs) {
+            Ok(product) => product,
+            Err(err) => panic!("{}", err),
         }
-        out
     }
 }
 
 impl<T: Scalar> std::ops::Index<usize> for Matrix<T> {
-    type Output = Vec<T>;
+    type Output = [T];
 
     fn index(&self, index: usize) -> &Self::Output {
-        &self.rows[index]
+        assert!(index < self.nrows, "Row index exceeds last row");
+        &self.data[index * self.ncols..(index + 1) * self.ncols]
     }
 }
 
 impl<T: Scalar> std::ops::IndexMut<usize> for Matrix<T> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.rows[index]
+        assert!(index < self.nrows, "Row index exceeds last row");
+        &mut self.data[index * self.ncols..(index + 1) * self.ncols]
+    }
+}
+
+impl<T: Scalar> std::ops::Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        assert!(col < self.ncols, "Column index exceeds last column");
+        &self[row][col]
+    }
+}
+
+impl<T: Scalar> std::ops::IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        assert!(col < self.ncols, "Column index exceeds last column");
+        &mut self[row][col]
     }
 }
 
 impl<T: Scalar> Matrix<T> {
-    fn nrows(&self) -> usize {
-        self.rows.len()
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, LinalgError> {
+        let nrows = rows.len();
+        let ncols = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != ncols) {
+            let (i, row) = rows
+                .iter()
+                .enumerate()
+                .find(|(_, row)| row.len() != ncols)
+                .unwrap();
+            return Err(LinalgError::InvalidArgument(format!(
+                "Row {} has {} entries, but the first row has {}",
+                i + 1,
+                row.len(),
+                ncols
+            )));
+        }
+        let data = rows.into_iter().flatten().collect();
+        Ok(Matrix { nrows, ncols, data })
+    }
+
+    pub fn from_vec(nrows: usize, ncols: usize, data: Vec<T>) -> Result<Self, LinalgError> {
+        if data.len() != nrows * ncols {
+            return Err(LinalgError::InvalidArgument(format!(
+                "{} entries cannot fill a {}×{} matrix",
+                data.len(),
+                nrows,
+                ncols
+            )));
+        }
+        Ok(Matrix { nrows, ncols, data })
     }
 
-    fn ncols(&self) -> usize {
-        self.rows[0].len()
+    pub fn nrows(&self) -> usize {
+        self.nrows
     }
 
-    fn is_square(&self) -> bool {
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    fn same_shape(&self, operation: &'static str, rhs: &Self) -> Result<(), LinalgError> {
+        if self.shape() != rhs.shape() {
+            return Err(LinalgError::mismatch(operation, self.shape(), rhs.shape()));
+        }
+        Ok(())
+    }
+
+    /// Like `+`, but reports mismatched shapes instead of panicking.
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        self.same_shape("add", rhs)?;
+        Ok(self + rhs)
+    }
+
+    /// Like `-`, but reports mismatched shapes instead of panicking.
+    pub fn checked_sub(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        self.same_shape("subtract", rhs)?;
+        Ok(self - rhs)
+    }
+
+    /// Like `*`, but reports mismatched shapes instead of panicking.
+    pub fn checked_mul(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        if self.ncols() != rhs.nrows() {
+            return Err(LinalgError::mismatch("multiply", self.shape(), rhs.shape()));
+        }
+        Ok(self.matmul(rhs))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.nrows).map(move |i| &self[i])
+    }
+
+    pub fn to_rows(&self) -> Vec<Vec<T>> {
+        self.rows().map(|row| row.to_vec()).collect()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn is_square(&self) -> bool {
         self.nrows() == self.ncols()
     }
 
     pub fn filled(rows: usize, cols: usize, value: T) -> Self {
-        let mut out: Vec<Vec<T>> = Vec::new();
-        let filled_row = vec![value; cols];
-        for _ in 0..rows {
-            out.push(filled_row.clone());
+        Matrix {
+            nrows: rows,
+            ncols: cols,
+            data: vec![value; rows * cols],
         }
-        Matrix { rows: out }
     }
 
     pub fn zero(rows: usize, cols: usize) -> Self {
@@ -223,7 +310,7 @@ impl<T: Scalar> Matrix<T> {
 
             rows.push(row);
         }
-        Matrix { rows }
+        Matrix::from_rows(rows).unwrap()
     }
 
     pub fn map<F, U>(&self, f: F) -> Matrix<U>
@@ -231,50 +318,51 @@ impl<T: Scalar> Matrix<T> {
         F: Fn(T) -> U,
         U: Scalar,
     {
-        let mapped_rows = self
-            .rows
-            .iter()
-            .map(|row| row.iter().map(|x| f(*x)).collect())
-            .collect();
-        Matrix { rows: mapped_rows }
-    }
-
-    fn get_column(&self, col: usize) -> Vector<T> {
-        let rows = self.nrows();
-        let mut out = Vector::zero(rows);
-        for i in 0..rows {
-            out[i] = self[i][col];
+        Matrix {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            data: self.data.iter().map(|x| f(*x)).collect(),
         }
-        out
     }
 
     pub fn transpose(&self) -> Self {
-        let mut out = Matrix::zero(self.ncols(), self.nrows());
-        for i in 0..self.nrows() {
-            for j in 0..self.ncols() {
-                out[j][i] = self[i][j];
-            }
+        let mut data = Vec::with_capacity(self.data.len());
+        for j in 0..self.ncols() {
+            data.extend(self.rows().map(|row| row[j]));
+        }
+        Matrix {
+            nrows: self.ncols,
+            ncols: self.nrows,
+            data,
         }
-        out
     }
 
     fn scale(&self, scalar: T) -> Self {
         let mut out = self.clone();
-        for i in 0..self.nrows() {
-            for j in 0..self.ncols() {
-                out[i][j] *= scalar;
-            }
+        for entry in out.data.iter_mut() {
+            *entry *= scalar;
         }
         out
     }
 
+    /// The largest magnitude counting as zero under `tol` for entries of
+    /// this matrix.
+    fn threshold(&self, tol: Tolerance) -> f64 {
+        tol.threshold(frobenius_norm(&self.data))
+    }
+
     pub fn is_symmetric(&self) -> bool {
+        self.is_symmetric_within(Tolerance::EXACT)
+    }
+
+    pub fn is_symmetric_within(&self, tol: Tolerance) -> bool {
         if !self.is_square() {
             return false;
         };
+        let threshold = self.threshold(tol);
         for i in 0..self.nrows() {
             for j in i + 1..self.ncols() {
-                if self[i][j] != self[j][i] {
+                if !(self[i][j] - self[j][i]).is_negligible(threshold) {
                     return false;
                 }
             }
@@ -283,12 +371,17 @@ impl<T: Scalar> Matrix<T> {
     }
 
     pub fn is_skew_symmetric(&self) -> bool {
+        self.is_skew_symmetric_within(Tolerance::EXACT)
+    }
+
+    pub fn is_skew_symmetric_within(&self, tol: Tolerance) -> bool {
         if !self.is_square() {
             return false;
         };
+        let threshold = self.threshold(tol);
         for i in 0..self.nrows() {
             for j in i..self.ncols() {
-                if self[i][j] != -self[j][i] {
+                if !(self[i][j] + self[j][i]).is_negligible(threshold) {
                     return false;
                 }
             }
@@ -297,12 +390,17 @@ impl<T: Scalar> Matrix<T> {
     }
 
     pub fn is_diagonal(&self) -> bool {
+        self.is_diagonal_within(Tolerance::EXACT)
+    }
+
+    pub fn is_diagonal_within(&self, tol: Tolerance) -> bool {
         if !self.is_square() {
             return false;
         };
+        let threshold = self.threshold(tol);
         for i in 0..self.nrows() {
             for j in 0..self.ncols() {
-                if i != j && !self[i][j].is_zero() {
+                if i != j && !self[i][j].is_negligible(threshold) {
                     return false;
                 }
             }
@@ -311,69 +409,106 @@ impl<T: Scalar> Matrix<T> {
     }
 
     pub fn is_lower_triangular(&self) -> bool {
+        self.is_lower_triangular_within(Tolerance::EXACT)
+    }
+
+    pub fn is_lower_triangular_within(&self, tol: Tolerance) -> bool {
         if !self.is_square() {
             return false;
         }
-        self.rows
-            .iter()
+        let threshold = self.threshold(tol);
+        self.rows()
             .enumerate()
-            .all(|(i, row)| row.iter().skip(i + 1).all(|x| x.is_zero()))
+            .all(|(i, row)| row.iter().skip(i + 1).all(|x| x.is_negligible(threshold)))
     }
 
     pub fn is_upper_triangular(&self) -> bool {
+        self.is_upper_triangular_within(Tolerance::EXACT)
+    }
+
+    pub fn is_upper_triangular_within(&self, tol: Tolerance) -> bool {
         if !self.is_square() {
             return false;
         }
-        self.rows
-            .iter()
+        let threshold = self.threshold(tol);
+        self.rows()
             .enumerate()
-            .all(|(i, row)| row.iter().take(i).all(|x| x.is_zero()))
+            .all(|(i, row)| row.iter().take(i).all(|x| x.is_negligible(threshold)))
     }
 
-    pub fn mul_vector(&self, v: &Vector<T>) -> Result<Vector<T>, String> {
+    pub fn mul_vector(&self, v: &Vector<T>) -> Result<Vector<T>, LinalgError> {
         if v.dim() != self.ncols() {
-            return Err("Vector does not have same dimension as matrix".to_owned());
-        }
-        let mut res = Vec::new();
-        for i in 0..self.nrows() {
-            let mut entry = T::zero();
-            for j in 0..self.ncols() {
-                entry += self[i][j] * v[j];
-            }
-            res.push(entry);
+            return Err(LinalgError::mismatch(
+                "multiply",
+                self.shape(),
+                (v.dim(), 1),
+            ));
         }
+        let res = self.rows().map(|row| T::dot(row, &v.entries)).collect();
         Ok(Vector { entries: res })
     }
 
-    pub fn rowswap(&self, r1: usize, r2: usize) -> Result<Self, String> {
-        if r1 >= self.nrows() || r2 >= self.nrows() {
-            return Err("Row index exceeds last row".to_owned());
+    fn check_row(&self, row: usize) -> Result<(), LinalgError> {
+        if row >= self.nrows() {
+            return Err(LinalgError::out_of_bounds("Row", row, self.nrows()));
         }
-        let mut out = self.clone();
-        out.rows.swap(r1, r2);
-        Ok(out)
+        Ok(())
     }
 
-    pub fn rowscale(&self, row: usize, c: T) -> Result<Self, String> {
-        if row >= self.nrows() {
-            return Err("Row index exceeds last row".to_owned());
+    pub fn rowswap_mut(&mut self, r1: usize, r2: usize) -> Result<(), LinalgError> {
+        self.check_row(r1)?;
+        self.check_row(r2)?;
+        if r1 == r2 {
+            return Ok(());
         }
-        let mut out = self.clone();
-        out[row] = out[row].iter().map(|entry| *entry * c).collect();
-        Ok(out)
+        let cols = self.ncols();
+        let (low, high) = (r1.min(r2), r1.max(r2));
+        let (head, tail) = self.data.split_at_mut(high * cols);
+        head[low * cols..(low + 1) * cols].swap_with_slice(&mut tail[..cols]);
+        Ok(())
     }
 
-    pub fn rowadd(&self, r1: usize, r2: usize, c: T) -> Result<Self, String> {
-        if r1 >= self.nrows() && r2 >= self.nrows() {
-            return Err("Row index exceeds last row".to_owned());
+    pub fn rowscale_mut(&mut self, row: usize, c: T) -> Result<(), LinalgError> {
+        self.check_row(row)?;
+        for entry in self[row].iter_mut() {
+            *entry *= c;
         }
+        Ok(())
+    }
+
+    pub fn rowadd_mut(&mut self, r1: usize, r2: usize, c: T) -> Result<(), LinalgError> {
+        self.check_row(r1)?;
+        self.check_row(r2)?;
         if r1 == r2 {
-            return Err("Cannot add a row to itself".to_owned());
+            return Err(LinalgError::invalid("Cannot add a row to itself"));
         }
+        let cols = self.ncols();
+        let (target, source) = if r1 < r2 {
+            let (head, tail) = self.data.split_at_mut(r2 * cols);
+            (&mut head[r1 * cols..(r1 + 1) * cols], &tail[..cols])
+        } else {
+            let (head, tail) = self.data.split_at_mut(r1 * cols);
+            (&mut tail[..cols], &head[r2 * cols..(r2 + 1) * cols])
+        };
+        T::axpy(target, c, source);
+        Ok(())
+    }
+
+    pub fn rowswap(&self, r1: usize, r2: usize) -> Result<Self, LinalgError> {
         let mut out = self.clone();
-        for (i, entry) in self[r2].iter().enumerate() {
-            out[r1][i] += c * *entry;
-        }
+        out.rowswap_mut(r1, r2)?;
+        Ok(out)
+    }
+
+    pub fn rowscale(&self, row: usize, c: T) -> Result<Self, LinalgError> {
+        let mut out = self.clone();
+        out.rowscale_mut(row, c)?;
+        Ok(out)
+    }
+
+    pub fn rowadd(&self, r1: usize, r2: usize, c: T) -> Result<Self, LinalgError> {
+        let mut out = self.clone();
+        out.rowadd_mut(r1, r2, c)?;
         Ok(out)
     }
 
@@ -387,72 +522,101 @@ impl<T: Scalar> Matrix<T> {
         out
     }
 
-    pub fn submatrix(&self, row: usize, col: usize) -> Result<Self, String> {
-        if row >= self.nrows() {
-            return Err("Cannot remove row that does not exist".to_owned());
-        }
+    pub fn submatrix(&self, row: usize, col: usize) -> Result<Self, LinalgError> {
+        self.check_row(row)?;
         if col >= self.ncols() {
-            return Err("Cannot remove column that does not exist".to_owned());
+            return Err(LinalgError::out_of_bounds("Column", col, self.ncols()));
         }
-        let mut out = Matrix { rows: vec![] };
-        for i in 0..self.nrows() {
+        let mut data = Vec::with_capacity((self.nrows() - 1) * (self.ncols() - 1));
+        for (i, mat_row) in self.rows().enumerate() {
             if i == row {
                 continue;
             }
-            let mut mat_row = self.rows[i].clone();
-            mat_row.remove(col);
-            out.rows.push(mat_row)
+            data.extend(
+                mat_row
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != col)
+                    .map(|(_, x)| *x),
+            );
         }
-        Ok(out)
+        Matrix::from_vec(self.nrows() - 1, self.ncols() - 1, data)
     }
 
     pub fn embed_matrix(&self, other: &Self, row: usize, col: usize) -> Self {
         let mut out = self.clone();
-        for i in 0..other.nrows() {
-            for j in 0..other.ncols() {
-                out[i + row][j + col] = other[i][j];
-            }
-        }
+        out.view_mut(row..row + other.nrows(), col..col + other.ncols())
+            .and_then(|mut block| block.copy_from(&other.as_view()))
+            .expect("Embedded matrix does not fit inside host");
         out
     }
 
-    fn augment_cols(&self, right: &Self) -> Result<Self, String> {
+    fn augment_cols(&self, right: &Self) -> Result<Self, LinalgError> {
         if self.nrows() != right.nrows() {
-            return Err("Cannot horizontally augment matrices of different heights".to_owned());
+            return Err(LinalgError::mismatch(
+                "augment horizontally",
+                self.shape(),
+                right.shape(),
+            ));
         }
-        let mut augmented = self.clone();
+        let mut data = Vec::with_capacity(self.nrows() * (self.ncols() + right.ncols()));
         for i in 0..self.nrows() {
-            augmented[i].extend(right[i].clone());
+            data.extend_from_slice(&self[i]);
+            data.extend_from_slice(&right[i]);
         }
-        Ok(augmented)
+        Matrix::from_vec(self.nrows(), self.ncols() + right.ncols(), data)
     }
 
-    fn augment_rows(&self, below: &Self) -> Result<Self, String> {
+    fn augment_rows(&self, below: &Self) -> Result<Self, LinalgError> {
         if self.ncols() != below.ncols() {
-            return Err("Cannot vertically augment matrices of different widths".to_owned());
+            return Err(LinalgError::mismatch(
+                "augment vertically",
+                self.shape(),
+                below.shape(),
+            ));
         }
         let mut augmented = self.clone();
-        for i in 0..below.nrows() {
-            augmented.rows.push(below[i].clone());
-        }
+        augmented.data.extend_from_slice(&below.data);
+        augmented.nrows += below.nrows();
         Ok(augmented)
     }
 
-    pub fn trace(&self) -> Result<T, String> {
+    pub fn trace(&self) -> Result<T, LinalgError> {
         if !self.is_square() {
-            return Err("Cannot compute trace of non-square matrix".to_owned());
+            return Err(LinalgError::not_square("take the trace of", self.shape()));
         }
-        let trace = self.rows.iter().enumerate().map(|(i, row)| row[i]).sum();
+        let trace = self.rows().enumerate().map(|(i, row)| row[i]).sum();
         Ok(trace)
     }
 
-    fn is_invertible(&self) -> bool {
-        self.is_square() && !self.echelon().0[self.nrows() - 1][self.ncols() - 1].is_zero()
+    /// The number of nonzero rows in the row echelon form, with entries
+    /// within `tol` of zero counted as zero.
+    pub fn rank_within(&self, tol: Tolerance) -> usize {
+        let (reduced, _) = self.echelon_within(tol);
+        reduced
+            .rows()
+            .filter(|row| row.iter().any(|x| !x.is_zero()))
+            .count()
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rank_within(Tolerance::default())
+    }
+
+    pub fn is_invertible_within(&self, tol: Tolerance) -> bool {
+        self.is_square() && self.rank_within(tol) == self.nrows()
     }
 
-    pub fn det(&self) -> Result<T, String> {
+    pub fn is_invertible(&self) -> bool {
+        self.is_invertible_within(Tolerance::default())
+    }
+
+    pub fn det(&self) -> Result<T, LinalgError> {
         if !self.is_square() {
-            return Err("Non-square matrix has no determinant".to_owned());
+            return Err(LinalgError::not_square(
+                "take the determinant of",
+                self.shape(),
+            ));
         }
         let (mat_ref, swaps) = self.echelon();
         let mut determinant = if swaps % 2 == 0 { T::one() } else { -T::one() };
@@ -463,46 +627,88 @@ impl<T: Scalar> Matrix<T> {
     }
 
     pub fn reduced_echelon(&self) -> Self {
-        let (mut out, _) = self.echelon();
+        self.reduced_echelon_within(Tolerance::default())
+    }
+
+    /// Reduced row echelon form, with entries within `tol` of zero treated as
+    /// zero, relative to the norm of this matrix.
+    pub fn reduced_echelon_within(&self, tol: Tolerance) -> Self {
+        let threshold = self.threshold(tol);
+        let (mut out, _) = self.echelon_within(Tolerance::Absolute(threshold));
         let rows = self.nrows();
         let cols = self.ncols();
+        if rows == 0 || cols == 0 {
+            return out;
+        }
         let mut pcol = 0;
 
         for row in 0..rows {
-            while out[row][pcol].is_zero() {
+            while out[row][pcol].is_negligible(threshold) {
                 pcol += 1;
                 if pcol >= cols {
                     return out;
                 }
             }
 
-            out = out.rowscale(row, T::one() / out[row][pcol]).unwrap();
+            let pivot = out[row][pcol];
+            out.rowscale_mut(row, T::one() / pivot).unwrap();
             out[row][pcol] = T::one();
 
             for i in 0..row {
-                out = out.rowadd(i, row, -out[i][pcol]).unwrap();
+                let mult = out[i][pcol];
+                out.rowadd_mut(i, row, -mult).unwrap();
                 out[i][pcol] = T::zero();
             }
         }
         out
     }
 
-    pub fn inverse(&self) -> Result<Self, String> {
-        if !self.is_invertible() {
-            return Err("Matrix is not invertible".to_owned());
+    pub fn inverse(&self) -> Result<Self, LinalgError> {
+        self.inverse_within(Tolerance::default())
+    }
+
+    /// Inverts the matrix, reporting it as singular if elimination finds no
+    /// pivot larger than `tol`, relative to the norm of this matrix.
+    pub fn inverse_within(&self, tol: Tolerance) -> Result<Self, LinalgError> {
+        if !self.is_square() {
+            return Err(LinalgError::not_square("invert", self.shape()));
         }
         let augmented = self.augment_cols(&Matrix::id(self.nrows())).unwrap();
-        let reduced = augmented.reduced_echelon();
-        let mut inverse_rows = Vec::new();
-        for row in &reduced.rows {
-            inverse_rows.push(row[self.ncols()..].to_vec())
+        let reduced = augmented.reduced_echelon_within(Tolerance::Absolute(self.threshold(tol)));
+        // The left block reduces to the identity exactly when the matrix is invertible.
+        let last = self.nrows().saturating_sub(1);
+        if self.nrows() > 0 && reduced[last][last].is_zero() {
+            return Err(LinalgError::Singular {
+                operation: "invert",
+            });
+        }
+        let mut inverse_data = Vec::with_capacity(self.nrows() * self.ncols());
+        for row in reduced.rows() {
+            inverse_data.extend_from_slice(&row[self.ncols()..]);
         }
-        Ok(Matrix { rows: inverse_rows })
+        Matrix::from_vec(self.nrows(), self.ncols(), inverse_data)
     }
 
-    pub fn powi(&self, power: i64) -> Result<Self, String> {
+    /// Solves `self * x = b` for a square invertible matrix.
+    pub fn solve(&self, b: &Vector<T>) -> Result<Vector<T>, LinalgError> {
         if !self.is_square() {
-            return Err("Cannot take powers of non-square matrix".to_owned());
+            return Err(LinalgError::not_square("solve exactly with", self.shape()));
+        }
+        if b.dim() != self.nrows() {
+            return Err(LinalgError::mismatch("solve", self.shape(), (b.dim(), 1)));
+        }
+        let reduced = self.augment_cols(&b.column_matrix())?.reduced_echelon();
+        let last = self.nrows().saturating_sub(1);
+        if self.nrows() > 0 && reduced[last][last].is_zero() {
+            return Err(LinalgError::Singular { operation: "solve" });
+        }
+        let solution = reduced.rows().map(|row| row[self.ncols()]).collect();
+        Ok(Vector { entries: solution })
+    }
+
+    pub fn powi(&self, power: i64) -> Result<Self, LinalgError> {
+        if !self.is_square() {
+            return Err(LinalgError::not_square("take powers of", self.shape()));
         }
         let mut mult = if power >= 0 {
             self.clone()
@@ -529,50 +735,45 @@ impl<T: Scalar> Matrix<T> {
 }
 
 impl<T: Scalar> Echelon for Matrix<T> {
-    default fn echelon(&self) -> (Self, usize) {
+    default fn echelon_within(&self, tol: Tolerance) -> (Self, usize) {
+        let threshold = self.threshold(tol);
         let mut out = self.clone();
         let rows = self.nrows();
         let cols = self.ncols();
         let mut prow = 0;
+        let mut pcol = 0;
         let mut swaps = 0;
 
-        for pcol in 0..cols {
-            if out[prow][pcol].is_zero() {
-                let mut pivot = false;
-                for i in prow + 1..rows {
-                    if !out[i][pcol].is_zero() {
-                        out = out.rowswap(prow, i).unwrap();
-                        swaps += 1;
-                        prow = i;
-                        pivot = true;
-                        break;
-                    }
-                }
-                if !pivot {
-                    continue;
+        while prow < rows && pcol < cols {
+            let pivot_row = (prow..rows).find(|&i| !out[i][pcol].is_negligible(threshold));
+            let Some(pivot_row) = pivot_row else {
+                for i in prow..rows {
+                    out[i][pcol] = T::zero();
                 }
-            }
-            if prow == rows - 1 {
-                break;
+                pcol += 1;
+                continue;
+            };
+            if prow != pivot_row {
+                out.rowswap_mut(prow, pivot_row).unwrap();
+                swaps += 1;
             }
 
-            for i in prow + 1..rows {
-                out = out
-                    .rowadd(i, prow, -out[i][pcol] / out[prow][pcol])
-                    .unwrap();
+            for i in (prow + 1)..rows {
+                let mult = out[i][pcol] / out[prow][pcol];
+                out.rowadd_mut(i, prow, -mult).unwrap();
                 out[i][pcol] = T::zero();
             }
+
             prow += 1;
-            if prow == rows - 1 {
-                break;
-            }
+            pcol += 1;
         }
         (out, swaps)
     }
 }
 
 impl Echelon for Matrix<f64> {
-    fn echelon(&self) -> (Self, usize) {
+    fn echelon_within(&self, tol: Tolerance) -> (Self, usize) {
+        let threshold = self.threshold(tol);
         let mut out = self.clone();
         let rows = self.nrows();
         let cols = self.ncols();
@@ -585,7 +786,7 @@ impl Echelon for Matrix<f64> {
             let mut max_leading = prow;
 
             for i in prow..rows {
-                if out.rows[i][pcol] != 0.0 {
+                if out[i][pcol].abs() > threshold {
                     leading_values.push(i);
                     if out[i][pcol].abs() > out[max_leading][pcol].abs() {
                         max_leading = i;
@@ -594,17 +795,20 @@ impl Echelon for Matrix<f64> {
             }
 
             if leading_values.is_empty() {
+                for i in prow..rows {
+                    out[i][pcol] = 0.0;
+                }
                 pcol += 1;
                 continue;
             }
             if prow != max_leading {
-                out = out.rowswap(prow, max_leading).unwrap();
+                out.rowswap_mut(prow, max_leading).unwrap();
                 swaps += 1
             }
 
             for i in (prow + 1)..rows {
                 let mult = out[i][pcol] / out[prow][pcol];
-                out = out.rowadd(i, prow, -mult).unwrap();
+                out.rowadd_mut(i, prow, -mult).unwrap();
                 out[i][pcol] = 0.0;
             }
 
@@ -616,9 +820,9 @@ impl Echelon for Matrix<f64> {
 }
 
 impl<T: Scalar + From<f64>> Matrix<T> {
-    pub fn exp(&self) -> Result<Self, String> {
+    pub fn exp(&self) -> Result<Self, LinalgError> {
         if !self.is_square() {
-            return Err("Cannot exponentiate non-square matrix".to_owned());
+            return Err(LinalgError::not_square("exponentiate", self.shape()));
         }
         let mut res = Matrix::id(self.nrows());
         let mut mult = self.clone();
@@ -630,6 +834,36 @@ impl<T: Scalar + From<f64>> Matrix<T> {
     }
 }
 
+/// Upper bound on the number of sweeps in the Jacobi SVD. Convergence is
+/// quadratic, so in practice fewer than ten are needed.
+const SVD_MAX_SWEEPS: usize = 60;
+
+/// A unit vector of dimension `dim` orthogonal to all of `basis`, which must
+/// be orthonormal and have fewer than `dim` elements.
+fn orthonormal_complement(basis: &[Vec<f64>], dim: usize) -> Vec<f64> {
+    // Of the standard basis vectors with `basis` projected out, the longest
+    // is the best conditioned.
+    (0..dim)
+        .map(|k| {
+            let mut candidate = vec![0.0; dim];
+            candidate[k] = 1.0;
+            // Projecting out twice keeps the result orthogonal to rounding error.
+            for _ in 0..2 {
+                for b in basis {
+                    let proj = f64::dot(&candidate, b);
+                    f64::axpy(&mut candidate, -proj, b);
+                }
+            }
+            candidate
+        })
+        .max_by(|a, b| f64::dot(a, a).total_cmp(&f64::dot(b, b)))
+        .map(|candidate| {
+            let norm = f64::dot(&candidate, &candidate).sqrt();
+            candidate.iter().map(|x| x / norm).collect()
+        })
+        .unwrap_or_default()
+}
+
 impl Matrix<f64> {
     fn givens_rotation(dim: usize, i: usize, j: usize, angle: f64) -> Self {
         let mut out = Matrix::id(dim);
@@ -651,7 +885,7 @@ impl Matrix<f64> {
     }
 
     pub fn rotation_y_3d(angle: f64) -> Self {
-        Matrix::givens_rotation(3, 2, 0, angle)
+        Matrix::givens_rotation(3, 0, 2, angle)
     }
 
     pub fn rotation_z_3d(angle: f64) -> Self {
@@ -668,50 +902,145 @@ impl Matrix<f64> {
         Matrix::id(dim) - n.outer_mul(&n).scale(2.0)
     }
 
-    pub fn qr_decomposition(&self) -> Result<(Self, Self), String> {
-        let big = self.ncols() >= 8 && self.nrows() >= 8;
+    pub fn qr_decomposition(&self) -> Result<(Self, Self), LinalgError> {
+        let rows = self.nrows();
         let cols = self.ncols();
-        let mut m = self.clone();
-        let mut p_matrices: Vec<Matrix<f64>> = vec![];
-        let dim = self.nrows();
+        let mut q = Matrix::id(rows);
+        let mut r = self.clone();
 
-        for i in 0..cols {
-            if i > 0 {
-                m = m.submatrix(0, 0).unwrap();
+        for i in 0..cols.min(rows) {
+            let v = r.view(i..rows, i..i + 1)?.to_vector();
+            // Nothing to eliminate below the diagonal, so the reflection can be skipped.
+            if v.entries.iter().skip(1).all(|x| *x == 0.0) {
+                continue;
             }
-            let v = m.get_column(0);
             let p = Matrix::<f64>::householder_standard(v);
 
-            // Fix for now, since something happens that shouldn't happen if not. Very slight undershoot (around 15th-16th decimal), but it generally works.
-            if p.ncols() <= 2 && big {
-                break;
+            let r_block = &p * &r.view(i..rows, i..cols)?.to_matrix();
+            r.view_mut(i..rows, i..cols)?
+                .copy_from(&r_block.as_view())?;
+            r.view_mut(i + 1..rows, i..i + 1)?.fill(0.0);
+            let q_block = &q.view(0..rows, i..rows)?.to_matrix() * &p;
+            q.view_mut(0..rows, i..rows)?
+                .copy_from(&q_block.as_view())?;
+        }
+        Ok((q, r))
+    }
+
+    /// The least squares solution of the overdetermined system `self * x = b`,
+    /// computed from the QR decomposition. The columns must be linearly
+    /// independent.
+    pub fn least_squares(&self, b: &Vector<f64>) -> Result<Vector<f64>, LinalgError> {
+        let (rows, cols) = (self.nrows(), self.ncols());
+        if b.dim() != rows {
+            return Err(LinalgError::mismatch("solve", self.shape(), (b.dim(), 1)));
+        }
+        if rows < cols {
+            return Err(LinalgError::invalid(
+                "Least squares needs at least as many equations as unknowns",
+            ));
+        }
+        let (q, r) = self.qr_decomposition()?;
+        let qtb = q.transpose().mul_vector(b)?;
+
+        // Back substitution in the square upper triangle of R.
+        let scale = r
+            .as_slice()
+            .iter()
+            .fold(0.0, |acc: f64, x| acc.max(x.abs()));
+        let mut x = Vector::zero(cols);
+        for i in (0..cols).rev() {
+            if r[i][i].abs() <= 1e-12 * scale {
+                return Err(LinalgError::Singular {
+                    operation: "solve least squares with linearly dependent columns",
+                });
             }
-            // println!("Current p matrix is:\n{}", p);
-            let embedded_p = Matrix::id(dim).embed_matrix(&p.clone(), i, i);
-            p_matrices.push(embedded_p.clone());
+            let tail = f64::dot(&r[i][i + 1..cols], &x.entries[i + 1..]);
+            x[i] = (qtb[i] - tail) / r[i][i];
+        }
+        Ok(x)
+    }
 
-            if i == cols - 1 {
-                continue;
+    /// The thin singular value decomposition `self = U Σ Vᵀ`, computed with
+    /// one-sided Jacobi rotations. Returns `U`, the singular values in
+    /// decreasing order and `V`, each with `min(nrows, ncols)` columns.
+    /// Columns of `U` belonging to zero singular values are completed to an
+    /// orthonormal set.
+    pub fn svd(&self) -> Result<(Self, Vector<f64>, Self), LinalgError> {
+        if self.nrows() < self.ncols() {
+            let (u, sigma, v) = self.transpose().svd()?;
+            return Ok((v, sigma, u));
+        }
+        let (m, n) = (self.nrows(), self.ncols());
+        // The rows of `work` are the columns of the matrix, and get rotated
+        // until they are mutually orthogonal. `vt` accumulates the rotations.
+        let mut work = self.transpose();
+        let mut vt = Matrix::id(n);
+        for _ in 0..SVD_MAX_SWEEPS {
+            let mut rotated = false;
+            for p in 0..n {
+                for q in p + 1..n {
+                    let alpha = f64::dot(&work[p], &work[p]);
+                    let beta = f64::dot(&work[q], &work[q]);
+                    let gamma = f64::dot(&work[p], &work[q]);
+                    if gamma.abs() <= f64::EPSILON * (alpha * beta).sqrt() {
+                        continue;
+                    }
+                    rotated = true;
+                    let zeta = (beta - alpha) / (2.0 * gamma);
+                    let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                    let c = 1.0 / (1.0 + t * t).sqrt();
+                    work.rotate_rows(p, q, c, t * c);
+                    vt.rotate_rows(p, q, c, t * c);
+                }
+            }
+            if !rotated {
+                break;
             }
-            m = p * m;
         }
-        let num_matrices = p_matrices.len();
-        let mut q = p_matrices[0].clone();
-        for i in 1..num_matrices {
-            q = q * p_matrices[i].clone();
-            // println!("Q is at step {}\n{}", i, q);
+
+        let norms: Vec<f64> = work.rows().map(|row| f64::dot(row, row).sqrt()).collect();
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| norms[j].total_cmp(&norms[i]));
+        let largest = order.first().map_or(0.0, |&i| norms[i]);
+        let negligible = m as f64 * f64::EPSILON * largest;
+
+        let mut u_rows: Vec<Vec<f64>> = Vec::with_capacity(n);
+        let mut v_rows = Vec::with_capacity(n);
+        let mut sigma = Vec::with_capacity(n);
+        for &i in &order {
+            if norms[i] > negligible {
+                u_rows.push(work[i].iter().map(|x| x / norms[i]).collect());
+                sigma.push(norms[i]);
+            } else {
+                u_rows.push(orthonormal_complement(&u_rows, m));
+                sigma.push(0.0);
+            }
+            v_rows.push(vt[i].to_vec());
         }
-        let mut r = p_matrices[num_matrices - 1].clone();
-        for i in (0..num_matrices - 1).rev() {
-            r = r * p_matrices[i].clone();
+        Ok((
+            Matrix::from_rows(u_rows)?.transpose(),
+            Vector::from(sigma),
+            Matrix::from_rows(v_rows)?.transpose(),
+        ))
+    }
+
+    /// Replaces rows `p` and `q` by `c * p - s * q` and `s * p + c * q`.
+    fn rotate_rows(&mut self, p: usize, q: usize, c: f64, s: f64) {
+        let cols = self.ncols;
+        let (head, tail) = self.data.split_at_mut(q * cols);
+        let row_p = &mut head[p * cols..(p + 1) * cols];
+        let row_q = &mut tail[..cols];
+        for (x, y) in row_p.iter_mut().zip(row_q.iter_mut()) {
+            let (a, b) = (*x, *y);
+            *x = c * a - s * b;
+            *y = s * a + c * b;
         }
-        r = r * self.clone();
-        Ok((q, r))
     }
 
-    pub fn eigens(&self) -> Result<(Vec<Vector<f64>>, Vec<Complex64>), String> {
+    pub fn eigens(&self) -> Result<(Vec<Vector<f64>>, Vec<Complex64>), LinalgError> {
         if !self.is_square() {
-            return Err("Eigenvalues can only be computed for square matrices!".to_owned());
+            return Err(LinalgError::not_square("find eigenvalues of", self.shape()));
         }
 
         todo!();
@@ -733,84 +1062,58 @@ impl Matrix<Complex64> {
         Matrix::id(dim) - n.adjoint_mul(&n).scale(2.0.into())
     }
 
-    pub fn qr_decomposition(&self) -> Result<(Self, Self), String> {
-        let big = self.ncols() >= 8 && self.nrows() >= 8;
+    pub fn qr_decomposition(&self) -> Result<(Self, Self), LinalgError> {
+        let rows = self.nrows();
         let cols = self.ncols();
-        let mut m = self.clone();
-        let mut p_matrices: Vec<Matrix<Complex64>> = vec![];
-        let dim = self.nrows();
+        let mut q = Matrix::id(rows);
+        let mut r = self.clone();
 
-        for i in 0..cols {
-            if i > 0 {
-                m = m.submatrix(0, 0).unwrap();
+        for i in 0..cols.min(rows) {
+            let v = r.view(i..rows, i..i + 1)?.to_vector();
+            // Nothing to eliminate below the diagonal, so the reflection can be skipped.
+            if v.entries.iter().skip(1).all(|x| *x == c64(0.0, 0.0)) {
+                continue;
             }
-            let v = m.get_column(0);
             let p = Matrix::<Complex64>::householder_standard(v);
 
-            // Fix for now, since something happens that shouldn't happen if not. Very slight undershoot (around 15th-16th decimal), but it generally works.
-            if p.ncols() <= 2 && big {
-                break;
-            }
-            let embedded_p = Matrix::id(dim).embed_matrix(&p.clone(), i, i);
-            p_matrices.push(embedded_p.clone());
-
-            if i == cols - 1 {
-                continue;
-            }
-            m = p * m;
+            let r_block = &p * &r.view(i..rows, i..cols)?.to_matrix();
+            r.view_mut(i..rows, i..cols)?
+                .copy_from(&r_block.as_view())?;
+            r.view_mut(i + 1..rows, i..i + 1)?.fill(c64(0.0, 0.0));
+            let q_block = &q.view(0..rows, i..rows)?.to_matrix() * &p;
+            q.view_mut(0..rows, i..rows)?
+                .copy_from(&q_block.as_view())?;
         }
-        let num_matrices = p_matrices.len();
-        let mut q = p_matrices[0].clone();
-        for i in 1..num_matrices {
-            q = q * p_matrices[i].clone();
-            // println!("Q is at step {}\n{}", i, q);
-        }
-        let mut r = p_matrices[num_matrices - 1].clone();
-        for i in (0..num_matrices - 1).rev() {
-            r = r * p_matrices[i].clone();
-        }
-        r = r * self.clone();
         Ok((q, r))
     }
 }
 
 impl<T: Scalar> Convertable for Matrix<T> {
-    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
-        let s = std::str::from_utf8(bytes).unwrap().to_owned();
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        let s = std::str::from_utf8(bytes)?;
 
         let mut rows: Vec<Vec<T>> = Vec::new();
-        let mut row_length = usize::MAX;
-        for row_str in s.split(';') {
+        for (i, row_str) in s.split(';').enumerate() {
             let mut row = Vec::new();
-            for entry in row_str.split(',') {
-                let res_float = T::from_str(entry);
-                match res_float {
-                    Ok(float) => row.push(float),
-                    Err(_) => return Err("Unable to parse number in matrix".to_owned()),
-                }
+            for (j, entry) in row_str.split(',').enumerate() {
+                row.push(parse_entry(entry, Position::Cell(i, j))?);
             }
-
-            if row_length == usize::MAX {
-                row_length = row.len();
-            } else if row.len() != row_length {
-                return Err("Non-rectangular matrix".to_owned());
-            }
-
             rows.push(row);
         }
-        Ok(Matrix { rows })
+        Matrix::from_rows(rows)
     }
 
     fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(&NumberFormat::ROUND_TRIP)
+    }
+
+    fn to_bytes_with(&self, format: &NumberFormat) -> Vec<u8> {
+        let scale = frobenius_norm(&self.data);
         let s = self
-            .rows
-            .iter()
+            .rows()
             .map(|row| {
                 row.iter()
-                    .map(|entry| {
-                        let entry_s = entry.to_string();
-                        truncate_zeroes(entry_s)
-                    })
+                    .map(|entry| entry.format_scaled(format, scale))
                     .collect::<Vec<String>>()
                     .join(",")
             })
@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use num::complex::Complex64;
+
+use crate::common::*;
+use crate::error::LinalgError;
+use crate::vector::{InnerProduct, Vector};
+
+/// Residuals shorter than this fraction of the original vector count as zero
+/// for floating point scalars.
+const DEPENDENCE_TOLERANCE: f64 = 1e-10;
+
+/// The order in which projections are subtracted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GramSchmidt {
+    /// Projects the original vector onto every earlier basis vector at once.
+    Classical,
+    /// Subtracts one projection at a time from the updated vector, which
+    /// loses much less orthogonality in floating point.
+    Modified,
+    /// Runs the classical process twice per vector, which is as accurate as
+    /// the modified one and projects in bulk.
+    Reorthogonalised,
+}
+
+impl FromStr for GramSchmidt {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classical" => Ok(GramSchmidt::Classical),
+            "modified" => Ok(GramSchmidt::Modified),
+            "reorthogonalised" => Ok(GramSchmidt::Reorthogonalised),
+            _ => Err(LinalgError::parse(
+                format!("Unknown Gram-Schmidt variant '{}'", s),
+                None,
+            )),
+        }
+    }
+}
+
+/// How the Gram–Schmidt process treats a scalar type: when a residual counts
+/// as zero, and whether basis vectors are normalised.
+pub trait GramSchmidtScalar: Scalar {
+    fn is_negligible(residual: &Vector<Self>, original: &Vector<Self>) -> bool;
+
+    fn finish(v: Vector<Self>) -> Vector<Self>;
+}
+
+/// Exact scalars compare with zero exactly and are not normalised, since
+/// normalising would need square roots.
+impl<T: Scalar> GramSchmidtScalar for T {
+    default fn is_negligible(residual: &Vector<T>, _original: &Vector<T>) -> bool {
+        residual.entries.iter().all(|x| x.is_zero())
+    }
+
+    default fn finish(v: Vector<T>) -> Vector<T> {
+        v
+    }
+}
+
+impl GramSchmidtScalar for f64 {
+    fn is_negligible(residual: &Vector<f64>, original: &Vector<f64>) -> bool {
+        residual.norm() <= DEPENDENCE_TOLERANCE * original.norm()
+    }
+
+    fn finish(v: Vector<f64>) -> Vector<f64> {
+        v.normalised()
+    }
+}
+
+impl GramSchmidtScalar for Complex64 {
+    fn is_negligible(residual: &Vector<Complex64>, original: &Vector<Complex64>) -> bool {
+        residual.norm() <= DEPENDENCE_TOLERANCE * original.norm()
+    }
+
+    fn finish(v: Vector<Complex64>) -> Vector<Complex64> {
+        v.normalised()
+    }
+}
+
+/// Subtracts from `v` its projections onto the mutually orthogonal `basis`,
+/// with all coefficients computed from `v` as it was passed in.
+fn project_out_classical<T: Scalar>(v: &Vector<T>, basis: &[Vector<T>]) -> Vector<T> {
+    let mut out = v.clone();
+    for u in basis {
+        let coefficient = v.inner(u) / u.inner(u);
+        out = &out - &u.scale(coefficient);
+    }
+    out
+}
+
+fn project_out_modified<T: Scalar>(v: &Vector<T>, basis: &[Vector<T>]) -> Vector<T> {
+    let mut out = v.clone();
+    for u in basis {
+        let coefficient = out.inner(u) / u.inner(u);
+        out = &out - &u.scale(coefficient);
+    }
+    out
+}
+
+/// Orthogonalises `vectors` in order with the Gram–Schmidt process.
+///
+/// Floating point results are orthonormal. Exact scalars such as `Fraction`
+/// are only orthogonalised, so the result stays exact. If some vectors are
+/// linearly dependent on the ones before them, the error lists their
+/// zero-based indices.
+pub fn gram_schmidt<T: GramSchmidtScalar>(
+    vectors: &[Vector<T>],
+    method: GramSchmidt,
+) -> Result<Vec<Vector<T>>, LinalgError> {
+    if let Some(first) = vectors.first() {
+        if vectors.iter().any(|v| v.dim() != first.dim()) {
+            return Err(LinalgError::invalid(
+                "All vectors must have the same dimension",
+            ));
+        }
+    }
+
+    // Projections use the unnormalised vectors, so that exact scalars stay
+    // exact; floating point ones are normalised at the end.
+    let mut basis: Vec<Vector<T>> = Vec::with_capacity(vectors.len());
+    let mut dependent = vec![];
+    for (i, v) in vectors.iter().enumerate() {
+        let residual = match method {
+            GramSchmidt::Classical => project_out_classical(v, &basis),
+            GramSchmidt::Modified => project_out_modified(v, &basis),
+            GramSchmidt::Reorthogonalised => {
+                project_out_classical(&project_out_classical(v, &basis), &basis)
+            }
+        };
+        if T::is_negligible(&residual, v) {
+            dependent.push(i);
+        } else {
+            basis.push(residual);
+        }
+    }
+
+    match dependent[..] {
+        [] => {}
+        [i] => {
+            return Err(LinalgError::invalid(&format!(
+                "Vector at index {} is linearly dependent on the vectors before it",
+                i
+            )))
+        }
+        _ => {
+            let indices: Vec<String> = dependent.iter().map(|i| i.to_string()).collect();
+            return Err(LinalgError::invalid(&format!(
+                "Vectors at indices {} are linearly dependent on the vectors before them",
+                indices.join(", ")
+            )));
+        }
+    }
+    Ok(basis.into_iter().map(T::finish).collect())
+}
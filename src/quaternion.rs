@@ -0,0 +1,259 @@
+use crate::convert::Convertable;
+use crate::error::LinalgError;
+use crate::format::{format_entries, NumberFormat};
+use crate::matrix::Matrix;
+use crate::vector::Vector;
+
+/// A quaternion `w + xi + yj + zk`. Unit quaternions represent rotations in
+/// three dimensions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Quaternion { w, x, y, z }
+    }
+
+    pub fn identity() -> Self {
+        Quaternion::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    pub fn norm(&self) -> f64 {
+        (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
+    /// The four-dimensional inner product of the components.
+    pub fn dot(&self, other: &Self) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn normalised(&self) -> Result<Self, LinalgError> {
+        let n = self.norm();
+        if n == 0.0 {
+            return Err(LinalgError::invalid(
+                "The zero quaternion cannot be normalised",
+            ));
+        }
+        Ok(Quaternion::new(
+            self.w / n,
+            self.x / n,
+            self.y / n,
+            self.z / n,
+        ))
+    }
+
+    /// The vector part `(x, y, z)`.
+    pub fn vector_part(&self) -> Vector<f64> {
+        Vector::from(vec![self.x, self.y, self.z])
+    }
+
+    /// The unit quaternion rotating by `angle` radians about `axis`.
+    pub fn from_axis_angle(axis: &Vector<f64>, angle: f64) -> Result<Self, LinalgError> {
+        if axis.dim() != 3 {
+            return Err(LinalgError::invalid("Rotation axis must be 3 dimensional"));
+        }
+        if axis.norm() == 0.0 {
+            return Err(LinalgError::invalid(
+                "Rotation axis cannot be the zero vector",
+            ));
+        }
+        let axis = axis.normalised();
+        let (s, c) = (angle / 2.0).sin_cos();
+        Ok(Quaternion::new(c, s * axis[0], s * axis[1], s * axis[2]))
+    }
+
+    /// Converts a 3×3 rotation matrix, or a 4×4 homogeneous matrix that only
+    /// rotates, into a unit quaternion with `w >= 0`.
+    pub fn from_rotation_matrix(mat: &Matrix<f64>) -> Result<Self, LinalgError> {
+        if mat.nrows() == 4 && mat.ncols() == 4 {
+            if mat[3][..3]
+                .iter()
+                .chain((0..3).map(|i| &mat[i][3]))
+                .any(|x| *x != 0.0)
+                || mat[3][3] != 1.0
+            {
+                return Err(LinalgError::invalid(
+                    "Homogeneous matrix must be a pure rotation",
+                ));
+            }
+            return Quaternion::from_rotation_matrix(&mat.view(0..3, 0..3)?.to_matrix());
+        }
+        mat.check_rotation()?;
+        let m = |i: usize, j: usize| mat[i][j];
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+        // Divide by the largest of the four candidate components to stay accurate.
+        let q = if trace > 0.0 {
+            let s = 2.0 * (1.0 + trace).sqrt();
+            Quaternion::new(
+                s / 4.0,
+                (m(2, 1) - m(1, 2)) / s,
+                (m(0, 2) - m(2, 0)) / s,
+                (m(1, 0) - m(0, 1)) / s,
+            )
+        } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+            let s = 2.0 * (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt();
+            Quaternion::new(
+                (m(2, 1) - m(1, 2)) / s,
+                s / 4.0,
+                (m(0, 1) + m(1, 0)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+            )
+        } else if m(1, 1) > m(2, 2) {
+            let s = 2.0 * (1.0 - m(0, 0) + m(1, 1) - m(2, 2)).sqrt();
+            Quaternion::new(
+                (m(0, 2) - m(2, 0)) / s,
+                (m(0, 1) + m(1, 0)) / s,
+                s / 4.0,
+                (m(1, 2) + m(2, 1)) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 - m(0, 0) - m(1, 1) + m(2, 2)).sqrt();
+            Quaternion::new(
+                (m(1, 0) - m(0, 1)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+                (m(1, 2) + m(2, 1)) / s,
+                s / 4.0,
+            )
+        };
+        Ok(if q.w < 0.0 { -q } else { q })
+    }
+
+    /// The 3×3 rotation matrix of a quaternion, which is normalised first.
+    pub fn to_rotation_matrix(self) -> Result<Matrix<f64>, LinalgError> {
+        if self.norm() == 0.0 {
+            return Err(LinalgError::invalid(
+                "The zero quaternion does not represent a rotation",
+            ));
+        }
+        let Quaternion { w, x, y, z } = self.normalised()?;
+        Matrix::from_rows(vec![
+            vec![
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            vec![
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            vec![
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ])
+    }
+
+    /// The 4×4 homogeneous rotation matrix of a quaternion.
+    pub fn to_homogeneous_matrix(self) -> Result<Matrix<f64>, LinalgError> {
+        self.to_rotation_matrix()?.homogeneous()
+    }
+
+    /// Normalised linear interpolation between two orientations. Cheaper than
+    /// `slerp`, but the angular speed is not constant.
+    pub fn nlerp(&self, other: &Self, t: f64) -> Result<Self, LinalgError> {
+        let other = self.nearest(other);
+        (*self * (1.0 - t) + other * t).normalised()
+    }
+
+    /// Spherical linear interpolation between two orientations, which turns at
+    /// constant angular speed along the shorter arc. `t = 0` gives `self` and
+    /// `t = 1` gives `other`, both normalised.
+    pub fn slerp(&self, other: &Self, t: f64) -> Result<Self, LinalgError> {
+        let (start, end) = (self.normalised()?, other.normalised()?);
+        let end = start.nearest(&end);
+        let cos = start.dot(&end).min(1.0);
+        // Nearly equal orientations make sin θ vanish; the chord and the arc
+        // agree there anyway.
+        if cos > 1.0 - 1e-6 {
+            return start.nlerp(&end, t);
+        }
+        let theta = cos.acos();
+        let sin = theta.sin();
+        Ok(start * (((1.0 - t) * theta).sin() / sin) + end * ((t * theta).sin() / sin))
+    }
+
+    /// `q` and `-q` are the same rotation; picks the one closer to `self`, so
+    /// that interpolation takes the shorter way round.
+    fn nearest(&self, other: &Self) -> Self {
+        if self.dot(other) < 0.0 {
+            -*other
+        } else {
+            *other
+        }
+    }
+}
+
+impl std::ops::Add for Quaternion {
+    type Output = Quaternion;
+    fn add(self, rhs: Quaternion) -> Self::Output {
+        Quaternion::new(
+            self.w + rhs.w,
+            self.x + rhs.x,
+            self.y + rhs.y,
+            self.z + rhs.z,
+        )
+    }
+}
+
+impl std::ops::Neg for Quaternion {
+    type Output = Quaternion;
+    fn neg(self) -> Self::Output {
+        Quaternion::new(-self.w, -self.x, -self.y, -self.z)
+    }
+}
+
+impl std::ops::Mul<f64> for Quaternion {
+    type Output = Quaternion;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Quaternion::new(self.w * rhs, self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+/// The Hamilton product. For unit quaternions `p * q` is the rotation `q`
+/// followed by `p`, matching the product of their matrices.
+impl std::ops::Mul for Quaternion {
+    type Output = Quaternion;
+    fn mul(self, rhs: Quaternion) -> Self::Output {
+        let (a, b) = (self, rhs);
+        Quaternion::new(
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        )
+    }
+}
+
+/// Quaternions are encoded like vectors, as `w,x,y,z`.
+impl Convertable for Quaternion {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        match Vector::<f64>::from_bytes(bytes)?.entries[..] {
+            [w, x, y, z] => Ok(Quaternion::new(w, x, y, z)),
+            _ => Err(LinalgError::invalid(
+                "Quaternion must have exactly four components",
+            )),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(&NumberFormat::ROUND_TRIP)
+    }
+
+    fn to_bytes_with(&self, format: &NumberFormat) -> Vec<u8> {
+        format_entries(&[self.w, self.x, self.y, self.z], format)
+            .join(",")
+            .as_bytes()
+            .to_vec()
+    }
+}
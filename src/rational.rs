@@ -0,0 +1,231 @@
+use std::fmt;
+use std::str::FromStr;
+
+use fraction::Fraction;
+
+use crate::error::{LinalgError, Position};
+use crate::format::NumberFormat;
+use crate::matrix::Matrix;
+
+/// Continued fraction expansions of doubles never need more terms than this
+/// before the remainder is lost to rounding.
+const MAX_TERMS: usize = 64;
+
+/// Limits on the exact forms recovered from floating point numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Recovery {
+    pub max_denominator: u64,
+    /// How far the exact form may be from the number, relative to the
+    /// number for values larger than 1.
+    pub tolerance: f64,
+    /// Surds `c·√r` are tried for squarefree `r` up to this; below 2 they
+    /// are not tried at all.
+    pub max_radicand: u64,
+}
+
+impl Default for Recovery {
+    fn default() -> Self {
+        Recovery {
+            max_denominator: 1000,
+            tolerance: 1e-9,
+            max_radicand: 10,
+        }
+    }
+}
+
+/// Parses comma separated options overriding the default: `den:n` for the
+/// largest denominator, `tol:x` for the tolerance and `surd:n` for the largest
+/// radicand. The empty string gives the default.
+impl FromStr for Recovery {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut recovery = Recovery::default();
+        for option in s.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            let err = || LinalgError::parse(format!("Invalid exact option '{}'", option), None);
+            let (key, value) = option.split_once(':').ok_or_else(err)?;
+            let value = value.trim();
+            match key.trim() {
+                "den" => recovery.max_denominator = value.parse().map_err(|_| err())?,
+                "tol" => recovery.tolerance = value.parse().map_err(|_| err())?,
+                "surd" => recovery.max_radicand = value.parse().map_err(|_| err())?,
+                _ => return Err(err()),
+            }
+        }
+        if recovery.max_denominator == 0 {
+            return Err(LinalgError::invalid(
+                "The largest denominator must be positive",
+            ));
+        }
+        Ok(recovery)
+    }
+}
+
+/// The fraction closest to `x` among those with denominator at most
+/// `max_denominator`, found from the convergents of its continued fraction
+/// and the best semiconvergent after the last one that fits.
+pub fn best_rational(x: f64, max_denominator: u64) -> Option<Fraction> {
+    if !x.is_finite() || x.abs() >= u64::MAX as f64 || max_denominator == 0 {
+        return None;
+    }
+    let target = x.abs();
+    // Convergents h/k, with (h0, k0) the one before (h1, k1).
+    let (mut h0, mut k0, mut h1, mut k1) = (0u64, 1u64, 1u64, 0u64);
+    let mut remainder = target;
+    for _ in 0..MAX_TERMS {
+        let a = remainder.floor() as u64;
+        let k2 = a.checked_mul(k1).and_then(|ak| ak.checked_add(k0));
+        let h2 = a.checked_mul(h1).and_then(|ah| ah.checked_add(h0));
+        let (Some(h2), Some(k2)) = (h2, k2) else {
+            break;
+        };
+        if k2 > max_denominator {
+            // The semiconvergent with the largest denominator that fits is
+            // the only other candidate for the best approximation.
+            let t = (max_denominator - k0) / k1;
+            let (hs, ks) = (t * h1 + h0, t * k1 + k0);
+            let error = |h: u64, k: u64| (target - h as f64 / k as f64).abs();
+            if error(hs, ks) < error(h1, k1) {
+                (h1, k1) = (hs, ks);
+            }
+            break;
+        }
+        (h0, k0, h1, k1) = (h1, k1, h2, k2);
+        let fractional = remainder - a as f64;
+        if fractional == 0.0 || (target - h1 as f64 / k1 as f64).abs() == 0.0 {
+            break;
+        }
+        remainder = 1.0 / fractional;
+    }
+    if k1 == 0 {
+        return None;
+    }
+    let fraction = Fraction::new(h1, k1);
+    Some(if x < 0.0 { -fraction } else { fraction })
+}
+
+fn fraction_to_f64(fraction: &Fraction) -> f64 {
+    match (fraction.numer(), fraction.denom()) {
+        (Some(&n), Some(&d)) => {
+            let value = n as f64 / d as f64;
+            if fraction.is_sign_negative() {
+                -value
+            } else {
+                value
+            }
+        }
+        _ => f64::NAN,
+    }
+}
+
+fn is_squarefree(n: u64) -> bool {
+    (2..)
+        .take_while(|p| p * p <= n)
+        .all(|p| !n.is_multiple_of(p * p))
+}
+
+/// An exact value recognised in a floating point number, or the number
+/// itself if none was found within the limits.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExactForm {
+    Rational(Fraction),
+    /// `coefficient · √radicand`, with a squarefree radicand of at least 2.
+    Surd {
+        coefficient: Fraction,
+        radicand: u64,
+    },
+    Inexact(f64),
+}
+
+impl Recovery {
+    fn within_tolerance(&self, x: f64, approximation: f64) -> bool {
+        (x - approximation).abs() <= self.tolerance * x.abs().max(1.0)
+    }
+
+    pub fn rational(&self, x: f64) -> Option<Fraction> {
+        let fraction = best_rational(x, self.max_denominator)?;
+        self.within_tolerance(x, fraction_to_f64(&fraction))
+            .then_some(fraction)
+    }
+
+    /// The simplest exact form of `x`: a fraction if there is one, otherwise
+    /// a surd with the smallest radicand.
+    pub fn exact_form(&self, x: f64) -> ExactForm {
+        if let Some(fraction) = self.rational(x) {
+            return ExactForm::Rational(fraction);
+        }
+        for radicand in (2..=self.max_radicand).filter(|&r| is_squarefree(r)) {
+            let root = (radicand as f64).sqrt();
+            let Some(coefficient) = best_rational(x / root, self.max_denominator) else {
+                continue;
+            };
+            if self.within_tolerance(x, fraction_to_f64(&coefficient) * root) {
+                return ExactForm::Surd {
+                    coefficient,
+                    radicand,
+                };
+            }
+        }
+        ExactForm::Inexact(x)
+    }
+}
+
+/// Writes fractions as `-3/4`, surds as `3√2/4` or `-√3`, and other numbers
+/// in the default `NumberFormat`.
+impl fmt::Display for ExactForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExactForm::Rational(fraction) => write!(f, "{}", fraction),
+            ExactForm::Surd {
+                coefficient,
+                radicand,
+            } => {
+                if coefficient.is_sign_negative() {
+                    write!(f, "-")?;
+                }
+                let numer = coefficient.numer().copied().unwrap_or(0);
+                let denom = coefficient.denom().copied().unwrap_or(1);
+                if numer != 1 {
+                    write!(f, "{}", numer)?;
+                }
+                write!(f, "√{}", radicand)?;
+                if denom != 1 {
+                    write!(f, "/{}", denom)?;
+                }
+                Ok(())
+            }
+            ExactForm::Inexact(x) => write!(f, "{}", NumberFormat::default().format(*x)),
+        }
+    }
+}
+
+impl Matrix<f64> {
+    /// Converts every entry to the closest fraction within the limits of
+    /// `recovery`, failing at the first entry that has none.
+    pub fn to_fractions(&self, recovery: &Recovery) -> Result<Matrix<Fraction>, LinalgError> {
+        let mut rows = Vec::with_capacity(self.nrows());
+        for (i, row) in self.rows().enumerate() {
+            let mut fractions = Vec::with_capacity(row.len());
+            for (j, &x) in row.iter().enumerate() {
+                let fraction = recovery.rational(x).ok_or_else(|| {
+                    LinalgError::InvalidArgument(format!(
+                        "No fraction with denominator at most {} is close to {} at {}",
+                        recovery.max_denominator,
+                        x,
+                        Position::Cell(i, j)
+                    ))
+                })?;
+                fractions.push(fraction);
+            }
+            rows.push(fractions);
+        }
+        Matrix::from_rows(rows)
+    }
+
+    /// The exact form of every entry, row by row.
+    pub fn to_exact_forms(&self, recovery: &Recovery) -> Vec<Vec<ExactForm>> {
+        self.rows()
+            .map(|row| row.iter().map(|&x| recovery.exact_form(x)).collect())
+            .collect()
+    }
+}
@@ -0,0 +1,585 @@
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal, Write};
+
+use fraction::Fraction;
+use num::complex::Complex64;
+
+use crate::cli::{matrix_to_text, matrix_to_vector, parse_matrix};
+use crate::common::*;
+use crate::error::LinalgError;
+use crate::format::{FormatScalar, NumberFormat};
+use crate::literal::parse_scalar;
+use crate::matrix::{Echelon, Matrix};
+use crate::tolerance::Tolerance;
+
+pub const HELP: &str = "\
+Statements:
+  A = [1,2;3,4]     define a variable, rows separated by ';'
+  inv(A)*A          evaluate an expression, kept as 'ans'
+Operators: + - * / and ^ with an integer exponent, such as A^-1.
+Functions: inv, det, rref, ref, rank, tr, transpose, solve(A, b), id(n).
+Commands:
+  :mode real|complex|exact   switch the entry type, recomputing variables
+  :vars                      list the variables
+  :history                   list the lines entered so far
+  :help, :quit";
+
+/// The entry type expressions are evaluated with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Real,
+    Complex,
+    Exact,
+}
+
+impl std::str::FromStr for Mode {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "real" => Ok(Mode::Real),
+            "complex" => Ok(Mode::Complex),
+            "exact" => Ok(Mode::Exact),
+            other => Err(LinalgError::InvalidArgument(format!(
+                "Unknown mode '{}', expected real, complex or exact",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Mode::Real => "real",
+            Mode::Complex => "complex",
+            Mode::Exact => "exact",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A number as `parse_scalar` reads it, such as `2.5e3` or `3i`.
+    Number(String),
+    Name(String),
+    /// The text between `[` and `]`.
+    Matrix(String),
+    Symbol(char),
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, LinalgError> {
+    let chars: Vec<char> = line.chars().collect();
+    let unexpected = |pos: usize| {
+        LinalgError::parse(
+            format!(
+                "Unexpected '{}' at character {} of '{}'",
+                chars[pos],
+                pos + 1,
+                line.trim()
+            ),
+            None,
+        )
+    };
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while pos < chars.len() {
+        let c = chars[pos];
+        let start = pos;
+        if c.is_whitespace() {
+            pos += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+                pos += 1;
+            }
+            // An exponent, only if digits follow so that `2e` stays an error.
+            if pos < chars.len() && matches!(chars[pos], 'e' | 'E') {
+                let mut end = pos + 1;
+                if end < chars.len() && matches!(chars[end], '+' | '-') {
+                    end += 1;
+                }
+                if end < chars.len() && chars[end].is_ascii_digit() {
+                    pos = end;
+                    while pos < chars.len() && chars[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+            }
+            if pos < chars.len() && matches!(chars[pos], 'i' | 'j') {
+                pos += 1;
+            }
+            if pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                return Err(unexpected(pos));
+            }
+            tokens.push(Token::Number(chars[start..pos].iter().collect()));
+        } else if c.is_alphabetic() || c == '_' {
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            tokens.push(Token::Name(chars[start..pos].iter().collect()));
+        } else if c == '[' {
+            let end = chars[start..]
+                .iter()
+                .position(|&c| c == ']')
+                .ok_or_else(|| {
+                    LinalgError::parse(format!("Missing ']' in '{}'", line.trim()), None)
+                })?;
+            tokens.push(Token::Matrix(
+                chars[start + 1..start + end].iter().collect(),
+            ));
+            pos = start + end + 1;
+        } else if "+-\u{2212}*/^(),=".contains(c) {
+            tokens.push(Token::Symbol(if c == '\u{2212}' { '-' } else { c }));
+            pos += 1;
+        } else {
+            return Err(unexpected(pos));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(String),
+    Matrix(String),
+    Variable(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Power(Box<Expr>, i64),
+    Call(String, Vec<Expr>),
+}
+
+/// A recursive descent parser, with the usual precedence: `^` binds
+/// tighter than unary minus, which binds tighter than `*` and `/`.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        let found = self.peek() == Some(&Token::Symbol(symbol));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), LinalgError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", symbol)))
+        }
+    }
+
+    fn error(&self, message: &str) -> LinalgError {
+        let found = match self.peek() {
+            Some(Token::Number(s) | Token::Name(s)) => format!("'{}'", s),
+            Some(Token::Matrix(s)) => format!("'[{}]'", s),
+            Some(Token::Symbol(c)) => format!("'{}'", c),
+            None => "the end of the line".to_owned(),
+        };
+        LinalgError::parse(format!("{}, found {}", message, found), None)
+    }
+
+    fn expression(&mut self) -> Result<Expr, LinalgError> {
+        let mut lhs = self.term()?;
+        while let Some(Token::Symbol(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, LinalgError> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Symbol(op @ ('*' | '/'))) = self.peek().cloned() {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, LinalgError> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.primary()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+        let negative = self.eat('-');
+        let power = match self.peek() {
+            Some(Token::Number(n)) => n.parse::<i64>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| self.error("Expected an integer exponent"))?;
+        self.pos += 1;
+        Ok(Expr::Power(
+            Box::new(base),
+            if negative { -power } else { power },
+        ))
+    }
+
+    fn primary(&mut self) -> Result<Expr, LinalgError> {
+        match self.peek().cloned() {
+            Some(Token::Number(n)) => {
+                self.pos += 1;
+                Ok(Expr::Number(n))
+            }
+            Some(Token::Matrix(m)) => {
+                self.pos += 1;
+                Ok(Expr::Matrix(m))
+            }
+            Some(Token::Name(name)) => {
+                self.pos += 1;
+                if !self.eat('(') {
+                    return Ok(Expr::Variable(name));
+                }
+                let mut args = vec![];
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Symbol('(')) => {
+                self.pos += 1;
+                let inner = self.expression()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            _ => Err(self.error("Expected a number, matrix, variable or '('")),
+        }
+    }
+}
+
+/// A line of input: an expression, and the variable it is assigned to.
+struct Statement {
+    name: Option<String>,
+    expr: Expr,
+}
+
+impl std::str::FromStr for Statement {
+    type Err = LinalgError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(line)?;
+        let name = match tokens.as_slice() {
+            [Token::Name(name), Token::Symbol('='), ..] => Some(name.clone()),
+            _ => None,
+        };
+        let mut parser = Parser {
+            tokens,
+            pos: if name.is_some() { 2 } else { 0 },
+        };
+        let expr = parser.expression()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("Expected an operator"));
+        }
+        Ok(Statement { name, expr })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value<T: Scalar> {
+    Scalar(T),
+    Matrix(Matrix<T>),
+}
+
+impl<T: Scalar> Value<T> {
+    fn matrix(self, function: &str) -> Result<Matrix<T>, LinalgError> {
+        match self {
+            Value::Matrix(mat) => Ok(mat),
+            Value::Scalar(_) => Err(LinalgError::InvalidArgument(format!(
+                "'{}' needs a matrix, found a scalar",
+                function
+            ))),
+        }
+    }
+
+    fn to_text(&self, format: &NumberFormat) -> String {
+        match self {
+            Value::Scalar(x) => x.format_with(format),
+            Value::Matrix(mat) => matrix_to_text(mat, format),
+        }
+    }
+}
+
+fn count<T: Scalar>(n: usize) -> T {
+    (0..n).map(|_| T::one()).sum()
+}
+
+/// The variables defined in one mode.
+struct Variables<T: Scalar> {
+    values: HashMap<String, Value<T>>,
+    tol: Tolerance,
+}
+
+impl<T: Scalar> Variables<T> {
+    fn new() -> Self {
+        Variables {
+            values: HashMap::new(),
+            tol: Tolerance::default(),
+        }
+    }
+
+    /// Evaluates the statement and stores its value, in `ans` if it is not
+    /// assigned to a variable.
+    fn execute(&mut self, statement: &Statement) -> Result<&Value<T>, LinalgError> {
+        let value = self.evaluate(&statement.expr)?;
+        let name = statement.name.clone().unwrap_or_else(|| "ans".to_owned());
+        self.values.insert(name.clone(), value);
+        Ok(&self.values[&name])
+    }
+
+    fn evaluate(&self, expr: &Expr) -> Result<Value<T>, LinalgError> {
+        Ok(match expr {
+            Expr::Number(n) => Value::Scalar(parse_scalar(n)?),
+            Expr::Matrix(m) => Value::Matrix(parse_matrix(m)?),
+            Expr::Variable(name) => self.values.get(name).cloned().ok_or_else(|| {
+                LinalgError::InvalidArgument(format!("Unknown variable '{}'", name))
+            })?,
+            Expr::Neg(inner) => match self.evaluate(inner)? {
+                Value::Scalar(x) => Value::Scalar(-x),
+                Value::Matrix(mat) => Value::Matrix(-mat),
+            },
+            Expr::Binary(op, lhs, rhs) => binary(*op, self.evaluate(lhs)?, self.evaluate(rhs)?)?,
+            Expr::Power(base, power) => match self.evaluate(base)? {
+                Value::Scalar(x) => {
+                    let powered = Matrix::from_vec(1, 1, vec![x])?.powi(*power)?;
+                    Value::Scalar(powered[0][0])
+                }
+                Value::Matrix(mat) => Value::Matrix(mat.powi(*power)?),
+            },
+            Expr::Call(function, args) => self.call(function, args)?,
+        })
+    }
+
+    fn call(&self, function: &str, args: &[Expr]) -> Result<Value<T>, LinalgError> {
+        let arity = if function == "solve" { 2 } else { 1 };
+        if args.len() != arity {
+            return Err(LinalgError::InvalidArgument(format!(
+                "'{}' takes {} argument{}, but {} {} given",
+                function,
+                arity,
+                if arity == 1 { "" } else { "s" },
+                args.len(),
+                if args.len() == 1 { "was" } else { "were" }
+            )));
+        }
+        if function == "id" {
+            // The dimension is a count, not an entry of the current mode.
+            return match &args[0] {
+                Expr::Number(n) => Ok(Value::Matrix(Matrix::id(n.parse().map_err(|_| {
+                    LinalgError::InvalidArgument(format!("Invalid dimension '{}'", n))
+                })?))),
+                _ => Err(LinalgError::invalid("'id' takes a whole number")),
+            };
+        }
+        let a = self.evaluate(&args[0])?.matrix(function)?;
+        let tol = self.tol;
+        Ok(match function {
+            "inv" => Value::Matrix(a.inverse_within(tol)?),
+            "det" => Value::Scalar(a.det()?),
+            "rref" => Value::Matrix(a.reduced_echelon_within(tol)),
+            "ref" => Value::Matrix(a.echelon_within(tol).0),
+            "rank" => Value::Scalar(count(a.rank_within(tol))),
+            "tr" => Value::Scalar(a.trace()?),
+            "transpose" => Value::Matrix(a.transpose()),
+            "solve" => {
+                let b = matrix_to_vector(self.evaluate(&args[1])?.matrix(function)?)?;
+                Value::Matrix(a.solve(&b)?.column_matrix())
+            }
+            _ => {
+                return Err(LinalgError::InvalidArgument(format!(
+                    "Unknown function '{}'",
+                    function
+                )))
+            }
+        })
+    }
+}
+
+fn binary<T: Scalar>(op: char, lhs: Value<T>, rhs: Value<T>) -> Result<Value<T>, LinalgError> {
+    use Value::{Matrix as M, Scalar as S};
+    Ok(match (op, lhs, rhs) {
+        ('+', S(x), S(y)) => S(x + y),
+        ('-', S(x), S(y)) => S(x - y),
+        ('*', S(x), S(y)) => S(x * y),
+        ('/', S(_), S(y)) | ('/', M(_), S(y)) if y.is_zero() => {
+            return Err(LinalgError::invalid("Division by zero"))
+        }
+        ('/', S(x), S(y)) => S(x / y),
+        ('+', M(a), M(b)) => M(a.checked_add(&b)?),
+        ('-', M(a), M(b)) => M(a.checked_sub(&b)?),
+        ('*', M(a), M(b)) => M(a.checked_mul(&b)?),
+        ('*', S(x), M(a)) | ('*', M(a), S(x)) => M(a.map(|y| x * y)),
+        ('/', M(a), S(x)) => M(a.map(|y| y / x)),
+        ('/', _, M(_)) => {
+            return Err(LinalgError::invalid(
+                "Cannot divide by a matrix, multiply by its inverse instead",
+            ))
+        }
+        _ => {
+            return Err(LinalgError::invalid(
+                "Cannot add or subtract a scalar and a matrix",
+            ))
+        }
+    })
+}
+
+/// The state of an interactive session. Assignments are replayed when the
+/// mode changes, so that variables keep their definitions in the new type.
+pub struct Session {
+    mode: Mode,
+    real: Variables<f64>,
+    complex: Variables<Complex64>,
+    exact: Variables<Fraction>,
+    /// The statements that evaluated successfully, in order.
+    definitions: Vec<String>,
+    history: Vec<String>,
+    pub format: NumberFormat,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            mode: Mode::Real,
+            real: Variables::new(),
+            complex: Variables::new(),
+            exact: Variables::new(),
+            definitions: vec![],
+            history: vec![],
+            format: NumberFormat::default(),
+        }
+    }
+}
+
+/// What a line of input led to.
+pub enum Outcome {
+    Output(String),
+    Quit,
+}
+
+impl Session {
+    fn execute(&mut self, statement: &Statement) -> Result<String, LinalgError> {
+        Ok(match self.mode {
+            Mode::Real => self.real.execute(statement)?.to_text(&self.format),
+            Mode::Complex => self.complex.execute(statement)?.to_text(&self.format),
+            Mode::Exact => self.exact.execute(statement)?.to_text(&self.format),
+        })
+    }
+
+    /// Switches the mode and re-evaluates the definitions in it, returning a
+    /// warning for every one that no longer evaluates.
+    fn switch_mode(&mut self, mode: Mode) -> Vec<String> {
+        self.mode = mode;
+        match mode {
+            Mode::Real => self.real = Variables::new(),
+            Mode::Complex => self.complex = Variables::new(),
+            Mode::Exact => self.exact = Variables::new(),
+        }
+        let mut warnings = vec![];
+        for definition in self.definitions.clone() {
+            let result = definition
+                .parse()
+                .and_then(|statement| self.execute(&statement));
+            if let Err(err) = result {
+                warnings.push(format!("warning: '{}' dropped: {}", definition, err));
+            }
+        }
+        warnings
+    }
+
+    fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = match self.mode {
+            Mode::Real => self.real.values.keys().cloned().collect(),
+            Mode::Complex => self.complex.values.keys().cloned().collect(),
+            Mode::Exact => self.exact.values.keys().cloned().collect(),
+        };
+        names.sort();
+        names
+    }
+
+    /// Runs one line of input, a statement or a `:` command.
+    pub fn run_line(&mut self, line: &str) -> Result<Outcome, LinalgError> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Outcome::Output(String::new()));
+        }
+        if line != ":history" {
+            self.history.push(line.to_owned());
+        }
+        let Some(command) = line.strip_prefix(':') else {
+            let statement: Statement = line.parse()?;
+            let output = self.execute(&statement)?;
+            self.definitions.push(line.to_owned());
+            return Ok(Outcome::Output(output));
+        };
+        let (command, argument) = command.split_once(' ').unwrap_or((command, ""));
+        Ok(Outcome::Output(match command {
+            "mode" if argument.trim().is_empty() => format!("mode: {}", self.mode),
+            "mode" => {
+                let mut lines = self.switch_mode(argument.parse()?);
+                lines.push(format!("mode: {}", self.mode));
+                lines.join("\n")
+            }
+            "vars" => self.names().join(", "),
+            "history" => self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(i, line)| format!("{:>4}  {}", i + 1, line))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            "help" => HELP.to_owned(),
+            "quit" | "q" => return Ok(Outcome::Quit),
+            _ => {
+                return Err(LinalgError::InvalidArgument(format!(
+                    "Unknown command ':{}', see :help",
+                    command
+                )))
+            }
+        }))
+    }
+}
+
+/// Reads lines from standard input until `:quit` or the end of input,
+/// prompting only when it is a terminal.
+pub fn run() {
+    let mut session = Session::default();
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        println!("linalg REPL, :help for help");
+    }
+    let prompt = || {
+        if interactive {
+            print!("> ");
+            let _ = std::io::stdout().flush();
+        }
+    };
+    prompt();
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        match session.run_line(&line) {
+            Ok(Outcome::Output(output)) if output.is_empty() => {}
+            Ok(Outcome::Output(output)) => println!("{}", output),
+            Ok(Outcome::Quit) => return,
+            Err(err) => eprintln!("error: {}", err),
+        }
+        prompt();
+    }
+}
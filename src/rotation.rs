@@ -0,0 +1,202 @@
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+use crate::error::LinalgError;
+use crate::matrix::Matrix;
+use crate::quaternion::Quaternion;
+use crate::vector::Vector;
+
+/// How far `RᵀR` and `det R` may stray from the identity and 1 before a matrix
+/// is no longer accepted as a rotation.
+const ROTATION_TOLERANCE: f64 = 1e-8;
+
+/// A sequence of three rotation axes for Euler angles, written like `"xyz"`.
+///
+/// Lowercase letters give extrinsic rotations about the fixed axes, applied
+/// in the written order. Uppercase letters give intrinsic rotations about the
+/// axes of the rotating frame, so `"XYZ"` is the same rotation as `"zyx"` with
+/// the angles reversed. Both Tait–Bryan (`"xyz"`) and proper Euler (`"zxz"`)
+/// sequences are supported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EulerConvention {
+    pub axes: [usize; 3],
+    pub intrinsic: bool,
+}
+
+impl FromStr for EulerConvention {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || LinalgError::parse(format!("Invalid Euler angle convention '{}'", s), None);
+        let chars: Vec<char> = s.trim().chars().collect();
+        if chars.len() != 3 {
+            return Err(err());
+        }
+        let intrinsic = chars.iter().all(|c| c.is_ascii_uppercase());
+        if !intrinsic && !chars.iter().all(|c| c.is_ascii_lowercase()) {
+            return Err(err());
+        }
+        let mut axes = [0; 3];
+        for (axis, c) in axes.iter_mut().zip(chars.iter()) {
+            *axis = match c.to_ascii_lowercase() {
+                'x' => 0,
+                'y' => 1,
+                'z' => 2,
+                _ => return Err(err()),
+            };
+        }
+        if axes[0] == axes[1] || axes[1] == axes[2] {
+            return Err(err());
+        }
+        Ok(EulerConvention { axes, intrinsic })
+    }
+}
+
+fn wrap_angle(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped <= -PI {
+        wrapped + 2.0 * PI
+    } else {
+        wrapped
+    }
+}
+
+impl Matrix<f64> {
+    fn rotation_about_axis(axis: usize, angle: f64) -> Self {
+        match axis {
+            0 => Matrix::rotation_x_3d(angle),
+            1 => Matrix::rotation_y_3d(angle),
+            _ => Matrix::rotation_z_3d(angle),
+        }
+    }
+
+    /// Checks that the matrix is a 3×3 rotation, i.e. orthogonal with determinant 1.
+    pub fn check_rotation(&self) -> Result<(), LinalgError> {
+        if self.nrows() != 3 || self.ncols() != 3 {
+            return Err(LinalgError::invalid("Rotation matrix must be 3x3"));
+        }
+        let deviation = &(&self.transpose() * self) - &Matrix::id(3);
+        if deviation
+            .as_slice()
+            .iter()
+            .any(|x| x.abs() > ROTATION_TOLERANCE)
+        {
+            return Err(LinalgError::invalid(
+                "Matrix is not orthogonal, so it is not a rotation",
+            ));
+        }
+        if (self.det()? - 1.0).abs() > ROTATION_TOLERANCE {
+            return Err(LinalgError::invalid(
+                "Matrix has determinant -1, so it is a reflection, not a rotation",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rodrigues' rotation by `angle` radians about `axis`, which need not be
+    /// normalised.
+    pub fn rotation_axis_angle(axis: &Vector<f64>, angle: f64) -> Result<Self, LinalgError> {
+        if axis.dim() != 3 {
+            return Err(LinalgError::invalid("Rotation axis must be 3 dimensional"));
+        }
+        if axis.norm() == 0.0 {
+            return Err(LinalgError::invalid(
+                "Rotation axis cannot be the zero vector",
+            ));
+        }
+        // R = cos θ I + sin θ [k]× + (1 - cos θ) k kᵀ
+        let k = axis.normalised();
+        let (s, c) = angle.sin_cos();
+        let mut out = k.outer_mul(&k);
+        for i in 0..3 {
+            for j in 0..3 {
+                out[i][j] *= 1.0 - c;
+            }
+            out[i][i] += c;
+        }
+        out[0][1] -= s * k[2];
+        out[0][2] += s * k[1];
+        out[1][0] += s * k[2];
+        out[1][2] -= s * k[0];
+        out[2][0] -= s * k[1];
+        out[2][1] += s * k[0];
+        Ok(out)
+    }
+
+    /// Extracts the unit axis and the angle in `[0, π]` of a rotation matrix.
+    /// The identity has angle 0, and its axis is reported as the z axis.
+    pub fn to_axis_angle(&self) -> Result<(Vector<f64>, f64), LinalgError> {
+        let q = Quaternion::from_rotation_matrix(self)?;
+        let v = q.vector_part();
+        let sin_half = v.norm();
+        if sin_half == 0.0 {
+            return Ok((Vector::standard_basis(3, 2), 0.0));
+        }
+        Ok((v.scale(1.0 / sin_half), 2.0 * sin_half.atan2(q.w)))
+    }
+
+    /// The rotation given by Euler angles in the given convention.
+    pub fn from_euler(angles: [f64; 3], convention: EulerConvention) -> Self {
+        let [r1, r2, r3] =
+            [0, 1, 2].map(|n| Matrix::rotation_about_axis(convention.axes[n], angles[n]));
+        if convention.intrinsic {
+            &(&r1 * &r2) * &r3
+        } else {
+            &(&r3 * &r2) * &r1
+        }
+    }
+
+    /// Decomposes a rotation matrix into Euler angles in the given convention.
+    ///
+    /// The middle angle lies in `[0, π]` for proper Euler sequences and in
+    /// `[-π/2, π/2]` for Tait–Bryan sequences, the others in `(-π, π]`. In
+    /// gimbal lock only the sum or difference of the outer angles is
+    /// determined, and the third angle of extrinsic sequences, or the first of
+    /// intrinsic ones, is set to zero.
+    pub fn to_euler(&self, convention: EulerConvention) -> Result<[f64; 3], LinalgError> {
+        let q = Quaternion::from_rotation_matrix(self)?;
+        // Work with the extrinsic sequence, following Bernardes and Viollet,
+        // "Quaternion to Euler angles conversion: A direct, general and
+        // computationally efficient method" (2022).
+        let mut axes = convention.axes;
+        if convention.intrinsic {
+            axes.reverse();
+        }
+        let (i, j) = (axes[0], axes[1]);
+        let proper = axes[0] == axes[2];
+        let k = if proper { 3 - i - j } else { axes[2] };
+        let sign =
+            ((i as i64 - j as i64) * (j as i64 - k as i64) * (k as i64 - i as i64) / 2) as f64;
+
+        let part = [q.x, q.y, q.z];
+        let (a, b, c, d) = if proper {
+            (q.w, part[i], part[j], part[k] * sign)
+        } else {
+            (
+                q.w - part[j],
+                part[i] + part[k] * sign,
+                part[j] + q.w,
+                part[k] * sign - part[i],
+            )
+        };
+
+        let mut middle = 2.0 * c.hypot(d).atan2(a.hypot(b));
+        let half_sum = b.atan2(a);
+        let half_diff = d.atan2(c);
+        let (mut first, mut third) = if middle.abs() <= ROTATION_TOLERANCE {
+            (2.0 * half_sum, 0.0)
+        } else if (middle - PI).abs() <= ROTATION_TOLERANCE {
+            (-2.0 * half_diff, 0.0)
+        } else {
+            (half_sum - half_diff, half_sum + half_diff)
+        };
+        if !proper {
+            third *= sign;
+            middle -= PI / 2.0;
+        }
+        if convention.intrinsic {
+            std::mem::swap(&mut first, &mut third);
+        }
+        Ok([wrap_angle(first), middle, wrap_angle(third)])
+    }
+}
@@ -0,0 +1,573 @@
+use crate::common::*;
+use crate::convert::{parse_entry, Convertable};
+use crate::error::{LinalgError, Position};
+use crate::format::{format_entries, FormatScalar, NumberFormat};
+use crate::matrix::Matrix;
+use crate::tolerance::{frobenius_norm, Approximate, Tolerance};
+use crate::vector::Vector;
+
+/// A matrix whose dimensions are known at compile time, stored inline.
+///
+/// Intended for the small transforms (2×2 up to 4×4) that are applied over and
+/// over again, where the heap allocations of `Matrix` dominate the cost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SMatrix<T, const R: usize, const C: usize>
+where
+    T: Scalar,
+{
+    pub rows: [[T; C]; R],
+}
+
+/// A vector whose dimension is known at compile time, stored inline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SVector<T, const N: usize>
+where
+    T: Scalar,
+{
+    pub entries: [T; N],
+}
+
+impl<T: Scalar, const R: usize, const C: usize> SMatrix<T, R, C> {
+    pub fn filled(value: T) -> Self {
+        SMatrix {
+            rows: [[value; C]; R],
+        }
+    }
+
+    pub fn zero() -> Self {
+        SMatrix::filled(T::zero())
+    }
+
+    pub fn transpose(&self) -> SMatrix<T, C, R> {
+        let mut out = SMatrix::zero();
+        for i in 0..R {
+            for j in 0..C {
+                out.rows[j][i] = self.rows[i][j];
+            }
+        }
+        out
+    }
+
+    pub fn scale(&self, scalar: T) -> Self {
+        let mut out = *self;
+        for row in out.rows.iter_mut() {
+            for entry in row.iter_mut() {
+                *entry *= scalar;
+            }
+        }
+        out
+    }
+
+    pub fn mul_vector(&self, v: &SVector<T, C>) -> SVector<T, R> {
+        let mut out = SVector::zero();
+        for i in 0..R {
+            for j in 0..C {
+                out.entries[i] += self.rows[i][j] * v.entries[j];
+            }
+        }
+        out
+    }
+}
+
+impl<T: Scalar, const N: usize> SMatrix<T, N, N> {
+    pub fn id() -> Self {
+        let mut out = SMatrix::zero();
+        for i in 0..N {
+            out.rows[i][i] = T::one();
+        }
+        out
+    }
+
+    pub fn trace(&self) -> T {
+        (0..N).map(|i| self.rows[i][i]).sum()
+    }
+
+    /// The row at or below `k` with the largest entry in column `k`, among
+    /// those above `threshold`.
+    fn pivot_row(&self, k: usize, threshold: f64) -> Option<usize> {
+        (k..N)
+            .filter(|&i| !self.rows[i][k].is_negligible(threshold))
+            .max_by(|&a, &b| {
+                self.rows[a][k]
+                    .magnitude()
+                    .total_cmp(&self.rows[b][k].magnitude())
+            })
+    }
+
+    fn threshold(&self, tol: Tolerance) -> f64 {
+        tol.threshold(frobenius_norm(self.rows.as_flattened()))
+    }
+
+    /// The determinant, by elimination with partial pivoting. Like
+    /// `Matrix::det`, pivots within the default tolerance count as zero.
+    pub fn det(&self) -> T {
+        let threshold = self.threshold(Tolerance::default());
+        let mut a = *self;
+        let mut det = T::one();
+        for k in 0..N {
+            let Some(p) = a.pivot_row(k, threshold) else {
+                return T::zero();
+            };
+            if p != k {
+                a.rows.swap(p, k);
+                det = -det;
+            }
+            let pivot = a.rows[k][k];
+            det *= pivot;
+            for i in k + 1..N {
+                let factor = a.rows[i][k] / pivot;
+                for j in k..N {
+                    let entry = a.rows[k][j];
+                    a.rows[i][j] -= factor * entry;
+                }
+            }
+        }
+        det
+    }
+
+    pub fn inverse(&self) -> Result<Self, LinalgError> {
+        self.inverse_within(Tolerance::default())
+    }
+
+    /// Inverts the matrix by Gauss-Jordan elimination, reporting it as
+    /// singular if no pivot is larger than `tol`, relative to its norm.
+    pub fn inverse_within(&self, tol: Tolerance) -> Result<Self, LinalgError> {
+        let threshold = self.threshold(tol);
+        let mut a = *self;
+        let mut inverse = SMatrix::id();
+        for k in 0..N {
+            let p = a.pivot_row(k, threshold).ok_or(LinalgError::Singular {
+                operation: "invert",
+            })?;
+            a.rows.swap(p, k);
+            inverse.rows.swap(p, k);
+            let scale = T::one() / a.rows[k][k];
+            for j in 0..N {
+                a.rows[k][j] *= scale;
+                inverse.rows[k][j] *= scale;
+            }
+            for i in (0..N).filter(|&i| i != k) {
+                let factor = a.rows[i][k];
+                for j in 0..N {
+                    let (entry, inverse_entry) = (a.rows[k][j], inverse.rows[k][j]);
+                    a.rows[i][j] -= factor * entry;
+                    inverse.rows[i][j] -= factor * inverse_entry;
+                }
+            }
+        }
+        Ok(inverse)
+    }
+
+    pub fn powi(&self, power: i64) -> Result<Self, LinalgError> {
+        let mut mult = if power >= 0 { *self } else { self.inverse()? };
+        let mut res = SMatrix::id();
+        let mut exponent = power.unsigned_abs();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                res = res * mult;
+            }
+            mult = mult * mult;
+            exponent >>= 1;
+        }
+        Ok(res)
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> std::ops::Add for SMatrix<T, R, C> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut out = self;
+        for i in 0..R {
+            for j in 0..C {
+                out.rows[i][j] += rhs.rows[i][j];
+            }
+        }
+        out
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> std::ops::Sub for SMatrix<T, R, C> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        let mut out = self;
+        for i in 0..R {
+            for j in 0..C {
+                out.rows[i][j] -= rhs.rows[i][j];
+            }
+        }
+        out
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> std::ops::Neg for SMatrix<T, R, C> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        self.scale(-T::one())
+    }
+}
+
+impl<T: Scalar, const R: usize, const K: usize, const C: usize> std::ops::Mul<SMatrix<T, K, C>>
+    for SMatrix<T, R, K>
+{
+    type Output = SMatrix<T, R, C>;
+    fn mul(self, rhs: SMatrix<T, K, C>) -> Self::Output {
+        let mut out = SMatrix::zero();
+        for i in 0..R {
+            for k in 0..K {
+                let a = self.rows[i][k];
+                for j in 0..C {
+                    out.rows[i][j] += a * rhs.rows[k][j];
+                }
+            }
+        }
+        out
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> std::ops::Mul<SVector<T, C>> for SMatrix<T, R, C> {
+    type Output = SVector<T, R>;
+    fn mul(self, rhs: SVector<T, C>) -> Self::Output {
+        self.mul_vector(&rhs)
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> std::ops::Index<usize> for SMatrix<T, R, C> {
+    type Output = [T; C];
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.rows[index]
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> std::ops::IndexMut<usize> for SMatrix<T, R, C> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.rows[index]
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> std::ops::Index<(usize, usize)>
+    for SMatrix<T, R, C>
+{
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        &self.rows[row][col]
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> std::ops::IndexMut<(usize, usize)>
+    for SMatrix<T, R, C>
+{
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        &mut self.rows[row][col]
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> TryFrom<&Matrix<T>> for SMatrix<T, R, C> {
+    type Error = LinalgError;
+
+    fn try_from(mat: &Matrix<T>) -> Result<Self, Self::Error> {
+        if mat.nrows() != R || mat.ncols() != C {
+            return Err(LinalgError::mismatch(
+                "convert between fixed-size and dynamic",
+                (R, C),
+                mat.shape(),
+            ));
+        }
+        let mut out = SMatrix::zero();
+        for (i, row) in mat.rows().enumerate() {
+            out.rows[i].copy_from_slice(row);
+        }
+        Ok(out)
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> From<SMatrix<T, R, C>> for Matrix<T> {
+    fn from(mat: SMatrix<T, R, C>) -> Self {
+        let data = mat.rows.iter().flatten().copied().collect();
+        Matrix::from_vec(R, C, data).unwrap()
+    }
+}
+
+impl<T: Scalar, const N: usize> SVector<T, N> {
+    pub fn zero() -> Self {
+        SVector {
+            entries: [T::zero(); N],
+        }
+    }
+
+    pub fn scale(&self, c: T) -> Self {
+        let mut out = *self;
+        for entry in out.entries.iter_mut() {
+            *entry *= c;
+        }
+        out
+    }
+
+    pub fn dot(&self, rhs: &Self) -> T {
+        self.entries
+            .iter()
+            .zip(rhs.entries.iter())
+            .map(|(a, b)| *a * *b)
+            .sum()
+    }
+}
+
+impl<T: Scalar, const N: usize> std::ops::Add for SVector<T, N> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut out = self;
+        for i in 0..N {
+            out.entries[i] += rhs.entries[i];
+        }
+        out
+    }
+}
+
+impl<T: Scalar, const N: usize> std::ops::Sub for SVector<T, N> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        let mut out = self;
+        for i in 0..N {
+            out.entries[i] -= rhs.entries[i];
+        }
+        out
+    }
+}
+
+impl<T: Scalar, const N: usize> std::ops::Neg for SVector<T, N> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        self.scale(-T::one())
+    }
+}
+
+impl<T: Scalar, const N: usize> std::ops::Index<usize> for SVector<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.entries[index]
+    }
+}
+
+impl<T: Scalar, const N: usize> std::ops::IndexMut<usize> for SVector<T, N> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.entries[index]
+    }
+}
+
+impl<T: Scalar, const N: usize> TryFrom<&Vector<T>> for SVector<T, N> {
+    type Error = LinalgError;
+
+    fn try_from(v: &Vector<T>) -> Result<Self, Self::Error> {
+        if v.dim() != N {
+            return Err(LinalgError::mismatch(
+                "convert between fixed-size and dynamic",
+                (N, 1),
+                (v.dim(), 1),
+            ));
+        }
+        let mut out = SVector::zero();
+        out.entries.copy_from_slice(&v.entries);
+        Ok(out)
+    }
+}
+
+impl<T: Scalar, const N: usize> From<SVector<T, N>> for Vector<T> {
+    fn from(v: SVector<T, N>) -> Self {
+        Vector::from(v.entries.to_vec())
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> Convertable for SMatrix<T, R, C> {
+    /// Parses the plugin's matrix format straight into the array, failing if
+    /// the shape is not `R`×`C`.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        let s = std::str::from_utf8(bytes)?;
+        let shape = text_shape(s);
+        if shape != (R, C) {
+            return Err(LinalgError::mismatch(
+                "convert between fixed-size and dynamic",
+                (R, C),
+                shape,
+            ));
+        }
+        let mut out = SMatrix::zero();
+        for (i, row_str) in s.split(';').enumerate() {
+            for (j, entry) in row_str.split(',').enumerate() {
+                out.rows[i][j] = parse_entry(entry, Position::Cell(i, j))?;
+            }
+        }
+        Ok(out)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(&NumberFormat::ROUND_TRIP)
+    }
+
+    fn to_bytes_with(&self, format: &NumberFormat) -> Vec<u8> {
+        let scale = frobenius_norm(self.rows.as_flattened());
+        let s = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|entry| entry.format_scaled(format, scale))
+                    .collect::<Vec<String>>()
+                    .join(",")
+            })
+            .collect::<Vec<String>>()
+            .join(";");
+        s.as_bytes().to_vec()
+    }
+}
+
+impl<T: Scalar, const N: usize> Convertable for SVector<T, N> {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        let s = std::str::from_utf8(bytes)?;
+        let dim = s.split(',').count();
+        if dim != N {
+            return Err(LinalgError::mismatch(
+                "convert between fixed-size and dynamic",
+                (N, 1),
+                (dim, 1),
+            ));
+        }
+        let mut out = SVector::zero();
+        for (i, entry) in s.split(',').enumerate() {
+            out.entries[i] = parse_entry(entry, Position::Entry(i))?;
+        }
+        Ok(out)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(&NumberFormat::ROUND_TRIP)
+    }
+
+    fn to_bytes_with(&self, format: &NumberFormat) -> Vec<u8> {
+        let s = format_entries(&self.entries, format).join(",");
+        s.as_bytes().to_vec()
+    }
+}
+
+/// The number of rows and of entries in every row of a matrix in the
+/// plugin's format, or `(rows, 0)` if the rows differ in length.
+fn text_shape(s: &str) -> (usize, usize) {
+    let mut rows = s.split(';').map(|row| row.split(',').count());
+    let cols = rows.next().unwrap_or(0);
+    let mut nrows = 1;
+    for len in rows {
+        if len != cols {
+            return (nrows, 0);
+        }
+        nrows += 1;
+    }
+    (nrows, cols)
+}
+
+fn mul_fixed<T: Scalar, const N: usize>(
+    m1_bytes: &[u8],
+    m2_bytes: &[u8],
+) -> Result<Vec<u8>, LinalgError> {
+    let a = SMatrix::<T, N, N>::from_bytes(m1_bytes)?;
+    let b = SMatrix::<T, N, N>::from_bytes(m2_bytes)?;
+    Ok((a * b).to_bytes())
+}
+
+fn mul_vector_fixed<T: Scalar, const N: usize>(
+    m_bytes: &[u8],
+    v_bytes: &[u8],
+) -> Result<Vec<u8>, LinalgError> {
+    let a = SMatrix::<T, N, N>::from_bytes(m_bytes)?;
+    let x = SVector::<T, N>::from_bytes(v_bytes)?;
+    Ok((a * x).to_bytes())
+}
+
+fn det_fixed<T: Scalar, const N: usize>(bytes: &[u8]) -> Result<Vec<u8>, LinalgError> {
+    let det = SMatrix::<T, N, N>::from_bytes(bytes)?.det();
+    Ok(det.format_with(&NumberFormat::ROUND_TRIP).into_bytes())
+}
+
+fn inverse_fixed<T: Scalar, const N: usize>(
+    bytes: &[u8],
+    tol: Tolerance,
+) -> Result<Vec<u8>, LinalgError> {
+    let a = SMatrix::<T, N, N>::from_bytes(bytes)?;
+    Ok(a.inverse_within(tol)?.to_bytes())
+}
+
+fn pow_fixed<T: Scalar, const N: usize>(bytes: &[u8], power: i64) -> Result<Vec<u8>, LinalgError> {
+    let a = SMatrix::<T, N, N>::from_bytes(bytes)?;
+    Ok(a.powi(power)?.to_bytes())
+}
+
+fn square_dim(bytes: &[u8]) -> Option<usize> {
+    let (nrows, ncols) = text_shape(std::str::from_utf8(bytes).ok()?);
+    (nrows == ncols).then_some(nrows)
+}
+
+/// Multiplies two square matrices given in the plugin's format without
+/// building a `Matrix`, if they are 2×2, 3×3 or 4×4. Other shapes give
+/// `None`, to be handled by the general product.
+pub fn mul_small<T: Scalar>(
+    m1_bytes: &[u8],
+    m2_bytes: &[u8],
+) -> Option<Result<Vec<u8>, LinalgError>> {
+    match (square_dim(m1_bytes)?, square_dim(m2_bytes)?) {
+        (2, 2) => Some(mul_fixed::<T, 2>(m1_bytes, m2_bytes)),
+        (3, 3) => Some(mul_fixed::<T, 3>(m1_bytes, m2_bytes)),
+        (4, 4) => Some(mul_fixed::<T, 4>(m1_bytes, m2_bytes)),
+        _ => None,
+    }
+}
+
+/// Applies a square matrix to a vector, both in the plugin's format, without
+/// building a `Matrix`, if it is 2×2, 3×3 or 4×4.
+pub fn mul_vector_small<T: Scalar>(
+    m_bytes: &[u8],
+    v_bytes: &[u8],
+) -> Option<Result<Vec<u8>, LinalgError>> {
+    let dim = std::str::from_utf8(v_bytes).ok()?.split(',').count();
+    match (square_dim(m_bytes)?, dim) {
+        (2, 2) => Some(mul_vector_fixed::<T, 2>(m_bytes, v_bytes)),
+        (3, 3) => Some(mul_vector_fixed::<T, 3>(m_bytes, v_bytes)),
+        (4, 4) => Some(mul_vector_fixed::<T, 4>(m_bytes, v_bytes)),
+        _ => None,
+    }
+}
+
+/// The determinant of a matrix in the plugin's format, computed without
+/// building a `Matrix` if it is 2×2, 3×3 or 4×4.
+pub fn det_small<T: Scalar>(bytes: &[u8]) -> Option<Result<Vec<u8>, LinalgError>> {
+    match square_dim(bytes)? {
+        2 => Some(det_fixed::<T, 2>(bytes)),
+        3 => Some(det_fixed::<T, 3>(bytes)),
+        4 => Some(det_fixed::<T, 4>(bytes)),
+        _ => None,
+    }
+}
+
+/// Inverts a matrix in the plugin's format without building a `Matrix`, if
+/// it is 2×2, 3×3 or 4×4.
+pub fn inverse_small<T: Scalar>(
+    bytes: &[u8],
+    tol: Tolerance,
+) -> Option<Result<Vec<u8>, LinalgError>> {
+    match square_dim(bytes)? {
+        2 => Some(inverse_fixed::<T, 2>(bytes, tol)),
+        3 => Some(inverse_fixed::<T, 3>(bytes, tol)),
+        4 => Some(inverse_fixed::<T, 4>(bytes, tol)),
+        _ => None,
+    }
+}
+
+/// Raises a matrix in the plugin's format to an integer power without
+/// building a `Matrix`, if it is 2×2, 3×3 or 4×4.
+pub fn pow_small<T: Scalar>(bytes: &[u8], power: i64) -> Option<Result<Vec<u8>, LinalgError>> {
+    match square_dim(bytes)? {
+        2 => Some(pow_fixed::<T, 2>(bytes, power)),
+        3 => Some(pow_fixed::<T, 3>(bytes, power)),
+        4 => Some(pow_fixed::<T, 4>(bytes, power)),
+        _ => None,
+    }
+}
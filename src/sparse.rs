@@ -0,0 +1,521 @@
+use crate::common::*;
+use crate::convert::{parse_entry, Convertable};
+use crate::error::{LinalgError, Position};
+use crate::format::{FormatScalar, NumberFormat};
+use crate::matrix::Matrix;
+use crate::tolerance::frobenius_norm;
+use crate::vector::{InnerProduct, Vector};
+
+/// A sparse matrix in coordinate form, meant for assembling a matrix entry by
+/// entry before compressing it. Repeated coordinates are summed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CooMatrix<T>
+where
+    T: Scalar,
+{
+    nrows: usize,
+    ncols: usize,
+    triplets: Vec<(usize, usize, T)>,
+}
+
+/// A sparse matrix in compressed sparse row form. The column indices and
+/// values of row `i` are stored at positions `indptr[i]..indptr[i + 1]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrMatrix<T>
+where
+    T: Scalar,
+{
+    nrows: usize,
+    ncols: usize,
+    indptr: Vec<usize>,
+    indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+/// A sparse matrix in compressed sparse column form. The row indices and
+/// values of column `j` are stored at positions `indptr[j]..indptr[j + 1]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CscMatrix<T>
+where
+    T: Scalar,
+{
+    nrows: usize,
+    ncols: usize,
+    indptr: Vec<usize>,
+    indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+type Compressed<T> = (Vec<usize>, Vec<usize>, Vec<T>);
+
+/// Compresses `(major, minor, value)` triplets along the major dimension,
+/// summing duplicates and dropping entries that end up zero.
+fn compress<T: Scalar>(
+    major_dim: usize,
+    triplets: impl Iterator<Item = (usize, usize, T)>,
+) -> Compressed<T> {
+    let mut buckets: Vec<Vec<(usize, T)>> = vec![vec![]; major_dim];
+    for (major, minor, value) in triplets {
+        buckets[major].push((minor, value));
+    }
+
+    let mut indptr = Vec::with_capacity(major_dim + 1);
+    let mut indices = vec![];
+    let mut values: Vec<T> = vec![];
+    indptr.push(0);
+    for mut bucket in buckets {
+        bucket.sort_by_key(|(minor, _)| *minor);
+        let start = indices.len();
+        for (minor, value) in bucket {
+            if indices.len() > start && indices[indices.len() - 1] == minor {
+                let last = values.len() - 1;
+                values[last] += value;
+            } else {
+                indices.push(minor);
+                values.push(value);
+            }
+        }
+        let mut kept = start;
+        for k in start..indices.len() {
+            if !values[k].is_zero() {
+                indices[kept] = indices[k];
+                values[kept] = values[k];
+                kept += 1;
+            }
+        }
+        indices.truncate(kept);
+        values.truncate(kept);
+        indptr.push(indices.len());
+    }
+    (indptr, indices, values)
+}
+
+impl<T: Scalar> CooMatrix<T> {
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        CooMatrix {
+            nrows,
+            ncols,
+            triplets: vec![],
+        }
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    pub fn push(&mut self, row: usize, col: usize, value: T) -> Result<(), LinalgError> {
+        if row >= self.nrows {
+            return Err(LinalgError::out_of_bounds("Row", row, self.nrows));
+        }
+        if col >= self.ncols {
+            return Err(LinalgError::out_of_bounds("Column", col, self.ncols));
+        }
+        self.triplets.push((row, col, value));
+        Ok(())
+    }
+
+    pub fn triplets(&self) -> &[(usize, usize, T)] {
+        &self.triplets
+    }
+
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let (indptr, indices, values) = compress(self.nrows, self.triplets.iter().copied());
+        CsrMatrix {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr,
+            indices,
+            values,
+        }
+    }
+
+    pub fn to_csc(&self) -> CscMatrix<T> {
+        let transposed = self.triplets.iter().map(|&(i, j, value)| (j, i, value));
+        let (indptr, indices, values) = compress(self.ncols, transposed);
+        CscMatrix {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr,
+            indices,
+            values,
+        }
+    }
+}
+
+impl<T: Scalar> From<&Matrix<T>> for CooMatrix<T> {
+    fn from(mat: &Matrix<T>) -> Self {
+        let mut coo = CooMatrix::new(mat.nrows(), mat.ncols());
+        for (i, row) in mat.rows().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                if !entry.is_zero() {
+                    coo.triplets.push((i, j, *entry));
+                }
+            }
+        }
+        coo
+    }
+}
+
+impl<T: Scalar> CsrMatrix<T> {
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn from_dense(mat: &Matrix<T>) -> Self {
+        CooMatrix::from(mat).to_csr()
+    }
+
+    /// Iterates over the stored entries of a row as `(column, value)` pairs.
+    pub fn row_entries(&self, row: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        let range = self.indptr[row]..self.indptr[row + 1];
+        self.indices[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[range].iter().copied())
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> T {
+        self.row_entries(row)
+            .find(|(j, _)| *j == col)
+            .map_or(T::zero(), |(_, value)| value)
+    }
+
+    pub fn to_coo(&self) -> CooMatrix<T> {
+        let mut coo = CooMatrix::new(self.nrows, self.ncols);
+        for i in 0..self.nrows {
+            coo.triplets
+                .extend(self.row_entries(i).map(|(j, value)| (i, j, value)));
+        }
+        coo
+    }
+
+    pub fn to_dense(&self) -> Matrix<T> {
+        let mut out = Matrix::zero(self.nrows, self.ncols);
+        for i in 0..self.nrows {
+            for (j, value) in self.row_entries(i) {
+                out[i][j] = value;
+            }
+        }
+        out
+    }
+
+    pub fn to_csc(&self) -> CscMatrix<T> {
+        self.to_coo().to_csc()
+    }
+
+    pub fn transpose(&self) -> Self {
+        // The column-compressed arrays of a matrix are the row-compressed arrays of its transpose.
+        let csc = self.to_csc();
+        CsrMatrix {
+            nrows: self.ncols,
+            ncols: self.nrows,
+            indptr: csc.indptr,
+            indices: csc.indices,
+            values: csc.values,
+        }
+    }
+
+    /// Whether the matrix equals its transpose, in both the stored entries
+    /// and their values.
+    pub fn is_symmetric(&self) -> bool {
+        self.nrows == self.ncols && *self == self.transpose()
+    }
+
+    pub fn diagonal(&self) -> Vector<T> {
+        let dim = self.nrows.min(self.ncols);
+        Vector::from((0..dim).map(|i| self.get(i, i)).collect::<Vec<T>>())
+    }
+
+    pub fn mul_vector(&self, v: &Vector<T>) -> Result<Vector<T>, LinalgError> {
+        if v.dim() != self.ncols {
+            return Err(LinalgError::mismatch(
+                "multiply",
+                (self.nrows, self.ncols),
+                (v.dim(), 1),
+            ));
+        }
+        let entries = (0..self.nrows)
+            .map(|i| self.row_entries(i).map(|(j, value)| value * v[j]).sum())
+            .collect::<Vec<T>>();
+        Ok(Vector::from(entries))
+    }
+
+    pub fn mul_dense(&self, rhs: &Matrix<T>) -> Result<Matrix<T>, LinalgError> {
+        if self.ncols != rhs.nrows() {
+            return Err(LinalgError::mismatch(
+                "multiply",
+                (self.nrows, self.ncols),
+                rhs.shape(),
+            ));
+        }
+        let mut out = Matrix::zero(self.nrows, rhs.ncols());
+        for i in 0..self.nrows {
+            for (k, value) in self.row_entries(i) {
+                for (entry, b) in out[i].iter_mut().zip(rhs[k].iter()) {
+                    *entry += value * *b;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn mul_sparse(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        if self.ncols != rhs.nrows {
+            return Err(LinalgError::mismatch(
+                "multiply",
+                (self.nrows, self.ncols),
+                (rhs.nrows, rhs.ncols),
+            ));
+        }
+        // Gustavson's algorithm: accumulate each output row in a dense scratch row.
+        let mut accumulator = vec![T::zero(); rhs.ncols];
+        let mut occupied = vec![false; rhs.ncols];
+        let mut touched = vec![];
+        let mut indptr = vec![0];
+        let mut indices = vec![];
+        let mut values = vec![];
+        for i in 0..self.nrows {
+            for (k, a) in self.row_entries(i) {
+                for (j, b) in rhs.row_entries(k) {
+                    if !occupied[j] {
+                        occupied[j] = true;
+                        touched.push(j);
+                    }
+                    accumulator[j] += a * b;
+                }
+            }
+            touched.sort_unstable();
+            for &j in &touched {
+                if !accumulator[j].is_zero() {
+                    indices.push(j);
+                    values.push(accumulator[j]);
+                }
+                accumulator[j] = T::zero();
+                occupied[j] = false;
+            }
+            touched.clear();
+            indptr.push(indices.len());
+        }
+        Ok(CsrMatrix {
+            nrows: self.nrows,
+            ncols: rhs.ncols,
+            indptr,
+            indices,
+            values,
+        })
+    }
+}
+
+impl CsrMatrix<f64> {
+    /// Solves `Ax = b` for a symmetric positive definite `A` with the conjugate
+    /// gradient method, preconditioned by the diagonal of `A`. Other matrices
+    /// are rejected where that is cheap to check, as the method may otherwise
+    /// stop at a wrong solution.
+    pub fn solve_cg(
+        &self,
+        b: &Vector<f64>,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<Vector<f64>, LinalgError> {
+        if self.nrows != self.ncols {
+            return Err(LinalgError::not_square(
+                "use conjugate gradients with",
+                (self.nrows, self.ncols),
+            ));
+        }
+        if b.dim() != self.nrows {
+            return Err(LinalgError::mismatch(
+                "solve",
+                (self.nrows, self.ncols),
+                (b.dim(), 1),
+            ));
+        }
+        if !self.is_symmetric() {
+            return Err(LinalgError::invalid(
+                "Conjugate gradient requires a symmetric matrix",
+            ));
+        }
+        let diagonal = self.diagonal();
+        if diagonal.entries.iter().any(|d| *d <= 0.0) {
+            return Err(LinalgError::invalid(
+                "Conjugate gradient requires a positive diagonal",
+            ));
+        }
+        let precondition = |r: &Vector<f64>| {
+            Vector::from(
+                r.entries
+                    .iter()
+                    .zip(diagonal.entries.iter())
+                    .map(|(x, d)| x / d)
+                    .collect::<Vec<f64>>(),
+            )
+        };
+
+        let b_norm = b.norm();
+        let mut x = Vector::zero(b.dim());
+        if b_norm == 0.0 {
+            return Ok(x);
+        }
+        let mut r = b.clone();
+        let mut z = precondition(&r);
+        let mut p = z.clone();
+        let mut rz = r.inner(&z);
+        for _ in 0..max_iter {
+            let ap = self.mul_vector(&p)?;
+            // A direction of zero or negative curvature only exists if `A`
+            // is not positive definite, and would divide by zero or step
+            // away from the solution.
+            let curvature = p.inner(&ap);
+            if curvature <= 0.0 || curvature.is_nan() {
+                return Err(LinalgError::invalid(
+                    "Conjugate gradient requires a positive definite matrix",
+                ));
+            }
+            let alpha = rz / curvature;
+            x = x + p.scale(alpha);
+            r = r - ap.scale(alpha);
+            if r.norm() <= tol * b_norm {
+                return Ok(x);
+            }
+            z = precondition(&r);
+            let rz_next = r.inner(&z);
+            p = z + p.scale(rz_next / rz);
+            rz = rz_next;
+        }
+        Err(LinalgError::NoConvergence {
+            method: "Conjugate gradient",
+            iterations: max_iter,
+        })
+    }
+}
+
+impl<T: Scalar> CscMatrix<T> {
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn from_dense(mat: &Matrix<T>) -> Self {
+        CooMatrix::from(mat).to_csc()
+    }
+
+    /// Iterates over the stored entries of a column as `(row, value)` pairs.
+    pub fn col_entries(&self, col: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        let range = self.indptr[col]..self.indptr[col + 1];
+        self.indices[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[range].iter().copied())
+    }
+
+    pub fn to_coo(&self) -> CooMatrix<T> {
+        let mut coo = CooMatrix::new(self.nrows, self.ncols);
+        for j in 0..self.ncols {
+            coo.triplets
+                .extend(self.col_entries(j).map(|(i, value)| (i, j, value)));
+        }
+        coo
+    }
+
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        self.to_coo().to_csr()
+    }
+
+    pub fn to_dense(&self) -> Matrix<T> {
+        let mut out = Matrix::zero(self.nrows, self.ncols);
+        for j in 0..self.ncols {
+            for (i, value) in self.col_entries(j) {
+                out[i][j] = value;
+            }
+        }
+        out
+    }
+
+    pub fn mul_vector(&self, v: &Vector<T>) -> Result<Vector<T>, LinalgError> {
+        if v.dim() != self.ncols {
+            return Err(LinalgError::mismatch(
+                "multiply",
+                (self.nrows, self.ncols),
+                (v.dim(), 1),
+            ));
+        }
+        let mut out = Vector::zero(self.nrows);
+        for j in 0..self.ncols {
+            for (i, value) in self.col_entries(j) {
+                out[i] += value * v[j];
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Sparse matrices are encoded as `nrows,ncols` followed by one `row,col,value`
+/// triplet per stored entry, all separated by `;`.
+impl<T: Scalar> Convertable for CsrMatrix<T> {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        let s = std::str::from_utf8(bytes)?;
+        let mut parts = s.split(';');
+        let shape = parts.next().unwrap_or("");
+        let (nrows, ncols) = match shape.split_once(',') {
+            Some((nrows, ncols)) => (
+                usize::from_bytes(nrows.as_bytes())?,
+                usize::from_bytes(ncols.as_bytes())?,
+            ),
+            None => {
+                return Err(LinalgError::invalid(
+                    "Sparse matrix must start with its dimensions",
+                ))
+            }
+        };
+
+        let mut coo = CooMatrix::new(nrows, ncols);
+        for (i, triplet_str) in parts.enumerate() {
+            let fields: Vec<&str> = triplet_str.split(',').collect();
+            if fields.len() != 3 {
+                return Err(LinalgError::invalid(
+                    "Sparse matrix entries must be row,col,value triplets",
+                ));
+            }
+            let row = usize::from_bytes(fields[0].as_bytes())?;
+            let col = usize::from_bytes(fields[1].as_bytes())?;
+            let value = parse_entry(fields[2], Position::Entry(i))?;
+            coo.push(row, col, value)?;
+        }
+        Ok(coo.to_csr())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(&NumberFormat::ROUND_TRIP)
+    }
+
+    fn to_bytes_with(&self, format: &NumberFormat) -> Vec<u8> {
+        let scale = frobenius_norm(&self.values);
+        let mut parts = vec![format!("{},{}", self.nrows, self.ncols)];
+        for i in 0..self.nrows {
+            for (j, value) in self.row_entries(i) {
+                let value = value.format_scaled(format, scale);
+                parts.push(format!("{},{},{}", i, j, value));
+            }
+        }
+        parts.join(";").as_bytes().to_vec()
+    }
+}
@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use num::complex::Complex64;
+
+use crate::common::*;
+use crate::error::LinalgError;
+
+/// The default relative tolerance, in units of machine epsilon. Elimination
+/// on well conditioned matrices leaves rounding errors a few hundred times
+/// the unit roundoff at most.
+const DEFAULT_EPSILONS: f64 = 1024.0;
+
+/// How close to zero a floating point quantity must be to count as zero.
+/// Exact scalars ignore it and compare with zero exactly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    /// Values of absolute value at most this count as zero.
+    Absolute(f64),
+    /// Values at most this times the Frobenius norm of the matrix involved
+    /// count as zero, so the decision does not depend on its scale.
+    Relative(f64),
+}
+
+impl Tolerance {
+    /// Zero tolerance, under which only exact zeros are zero.
+    pub const EXACT: Tolerance = Tolerance::Absolute(0.0);
+
+    /// The largest magnitude counting as zero next to values of size `norm`.
+    pub fn threshold(self, norm: f64) -> f64 {
+        match self {
+            Tolerance::Absolute(tol) => tol,
+            Tolerance::Relative(tol) => tol * norm,
+        }
+    }
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance::Relative(DEFAULT_EPSILONS * f64::EPSILON)
+    }
+}
+
+/// Parses `abs:1e-9` or `rel:1e-12`; the empty string gives the default.
+impl FromStr for Tolerance {
+    type Err = LinalgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(Tolerance::default());
+        }
+        let err = || {
+            LinalgError::parse(
+                format!("Expected 'abs:tol' or 'rel:tol', found '{}'", s),
+                None,
+            )
+        };
+        let (kind, value) = s.split_once(':').ok_or_else(err)?;
+        let value = value.trim().parse::<f64>().map_err(|_| err())?;
+        if value < 0.0 || value.is_nan() {
+            return Err(LinalgError::invalid(
+                "Tolerances must be nonnegative numbers",
+            ));
+        }
+        match kind.trim() {
+            "abs" => Ok(Tolerance::Absolute(value)),
+            "rel" => Ok(Tolerance::Relative(value)),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// Scalars that can be compared with zero up to a threshold.
+pub trait Approximate: Scalar {
+    /// The absolute value, or zero for exact scalars, which never need it.
+    fn magnitude(&self) -> f64;
+
+    fn is_negligible(&self, threshold: f64) -> bool;
+}
+
+impl<T: Scalar> Approximate for T {
+    default fn magnitude(&self) -> f64 {
+        0.0
+    }
+
+    default fn is_negligible(&self, _threshold: f64) -> bool {
+        self.is_zero()
+    }
+}
+
+impl Approximate for f64 {
+    fn magnitude(&self) -> f64 {
+        self.abs()
+    }
+
+    fn is_negligible(&self, threshold: f64) -> bool {
+        self.abs() <= threshold
+    }
+}
+
+impl Approximate for Complex64 {
+    fn magnitude(&self) -> f64 {
+        self.norm()
+    }
+
+    fn is_negligible(&self, threshold: f64) -> bool {
+        self.norm() <= threshold
+    }
+}
+
+/// The Frobenius norm of `entries`, or zero for exact scalars.
+pub fn frobenius_norm<T: Scalar>(entries: &[T]) -> f64 {
+    entries
+        .iter()
+        .map(|x| x.magnitude() * x.magnitude())
+        .sum::<f64>()
+        .sqrt()
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+
+use crate::common::*;
+use crate::convert::matrix_to_json;
+use crate::format::{FormatScalar, NumberFormat};
+use crate::matrix::Matrix;
+
+/// An elementary row operation. Rows are indexed from zero, but displayed
+/// from one, as in `R2 ← R2 − 3·R1`, with factors in the default
+/// `NumberFormat`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowOp<T: Scalar> {
+    Swap(usize, usize),
+    Scale(usize, T),
+    /// Adds `factor` times row `source` to row `target`.
+    Add {
+        target: usize,
+        source: usize,
+        factor: T,
+    },
+}
+
+impl<T: Scalar> fmt::Display for RowOp<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RowOp::Swap(r1, r2) => write!(f, "R{} ↔ R{}", r1 + 1, r2 + 1),
+            RowOp::Scale(row, c) => {
+                let c = c.format_with(&NumberFormat::default());
+                let c = match c.strip_prefix('-') {
+                    Some(magnitude) => format!("−{}", magnitude),
+                    None => c,
+                };
+                write!(f, "R{} ← {}·R{}", row + 1, c, row + 1)
+            }
+            RowOp::Add {
+                target,
+                source,
+                factor,
+            } => {
+                let factor = factor.format_with(&NumberFormat::default());
+                let (sign, magnitude) = match factor.strip_prefix('-') {
+                    Some(magnitude) => ("−", magnitude),
+                    None => ("+", factor.as_str()),
+                };
+                let coefficient = match magnitude {
+                    "1" => "".to_owned(),
+                    _ => format!("{}·", magnitude),
+                };
+                write!(
+                    f,
+                    "R{} ← R{} {} {}R{}",
+                    target + 1,
+                    target + 1,
+                    sign,
+                    coefficient,
+                    source + 1
+                )
+            }
+        }
+    }
+}
+
+/// One step of a traced elimination: the operation and the matrix after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step<T: Scalar> {
+    pub op: RowOp<T>,
+    pub matrix: Matrix<T>,
+}
+
+/// Applies row operations to a matrix and records them.
+struct Tracer<T: Scalar> {
+    current: Matrix<T>,
+    steps: Vec<Step<T>>,
+}
+
+impl<T: Scalar> Tracer<T> {
+    /// Performs and records `op`. An entry the operation makes exactly zero
+    /// or one can be given as `settled`, to cancel rounding in floating point.
+    fn apply(&mut self, op: RowOp<T>, settled: Option<(usize, usize, T)>) {
+        match op {
+            RowOp::Swap(r1, r2) => self.current.rowswap_mut(r1, r2),
+            RowOp::Scale(row, c) => self.current.rowscale_mut(row, c),
+            RowOp::Add {
+                target,
+                source,
+                factor,
+            } => self.current.rowadd_mut(target, source, factor),
+        }
+        .unwrap();
+        if let Some((i, j, value)) = settled {
+            self.current[i][j] = value;
+        }
+        self.steps.push(Step {
+            op,
+            matrix: self.current.clone(),
+        });
+    }
+
+    /// Clears the entries of column `pcol` in `rows` using the pivot row.
+    fn eliminate(&mut self, prow: usize, pcol: usize, rows: impl Iterator<Item = usize>) {
+        for i in rows {
+            if self.current[i][pcol].is_zero() {
+                continue;
+            }
+            let factor = -self.current[i][pcol] / self.current[prow][pcol];
+            let op = RowOp::Add {
+                target: i,
+                source: prow,
+                factor,
+            };
+            self.apply(op, Some((i, pcol, T::zero())));
+        }
+    }
+
+    /// Row echelon form, taking the first nonzero entry of each column as
+    /// the pivot, which keeps hand-checkable steps. Returns the pivot positions.
+    fn echelon(&mut self) -> Vec<(usize, usize)> {
+        let (rows, cols) = (self.current.nrows(), self.current.ncols());
+        let mut pivots = vec![];
+        let (mut prow, mut pcol) = (0, 0);
+        while prow < rows && pcol < cols {
+            let Some(pivot_row) = (prow..rows).find(|&i| !self.current[i][pcol].is_zero()) else {
+                pcol += 1;
+                continue;
+            };
+            if pivot_row != prow {
+                self.apply(RowOp::Swap(prow, pivot_row), None);
+            }
+            self.eliminate(prow, pcol, prow + 1..rows);
+            pivots.push((prow, pcol));
+            prow += 1;
+            pcol += 1;
+        }
+        pivots
+    }
+}
+
+impl<T: Scalar> Matrix<T> {
+    fn trace_elimination(&self, reduced: bool) -> Vec<Step<T>> {
+        let mut tracer = Tracer {
+            current: self.clone(),
+            steps: vec![],
+        };
+        let pivots = tracer.echelon();
+        if reduced {
+            for (prow, pcol) in pivots {
+                let pivot = tracer.current[prow][pcol];
+                if pivot != T::one() {
+                    let op = RowOp::Scale(prow, T::one() / pivot);
+                    tracer.apply(op, Some((prow, pcol, T::one())));
+                }
+                tracer.eliminate(prow, pcol, 0..prow);
+            }
+        }
+        tracer.steps
+    }
+
+    /// The row operations bringing the matrix to row echelon form, each with
+    /// the matrix after it. Over exact scalars such as `Fraction` every
+    /// intermediate matrix is exact.
+    pub fn echelon_traced(&self) -> Vec<Step<T>> {
+        self.trace_elimination(false)
+    }
+
+    /// Like `echelon_traced`, followed by the steps to reduced row echelon
+    /// form: scaling each pivot to one and clearing the entries above it.
+    pub fn reduced_echelon_traced(&self) -> Vec<Step<T>> {
+        self.trace_elimination(true)
+    }
+}
+
+/// Serialises a trace as JSON, with entries and factors as strings so that
+/// fractions survive: `{"start": m, "steps": [{"op": "add", "target": 1,
+/// "source": 0, "factor": "-3", "text": "R2 ← R2 − 3·R1", "matrix": m}, ...]}`.
+/// Row indices start at zero; `"swap"` steps have `rows` and `"scale"` steps
+/// `row` and `factor`.
+pub fn steps_to_json<T: Scalar>(start: &Matrix<T>, steps: &[Step<T>]) -> String {
+    let format = NumberFormat::default();
+    let steps: Vec<String> = steps
+        .iter()
+        .map(|step| {
+            let op = match &step.op {
+                RowOp::Swap(r1, r2) => format!("\"op\":\"swap\",\"rows\":[{},{}]", r1, r2),
+                RowOp::Scale(row, c) => {
+                    let c = c.format_with(&format);
+                    format!("\"op\":\"scale\",\"row\":{},\"factor\":\"{}\"", row, c)
+                }
+                RowOp::Add {
+                    target,
+                    source,
+                    factor,
+                } => format!(
+                    "\"op\":\"add\",\"target\":{},\"source\":{},\"factor\":\"{}\"",
+                    target,
+                    source,
+                    factor.format_with(&format)
+                ),
+            };
+            format!(
+                "{{{},\"text\":\"{}\",\"matrix\":{}}}",
+                op,
+                step.op,
+                matrix_to_json(&step.matrix)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"start\":{},\"steps\":[{}]}}",
+        matrix_to_json(start),
+        steps.join(",")
+    )
+}
@@ -0,0 +1,311 @@
+use crate::error::LinalgError;
+use crate::matrix::Matrix;
+use crate::smatrix::{SMatrix, SVector};
+use crate::vector::{InnerProduct, Vector};
+
+/// Rejects a viewing volume with no extent along some axis, whose projection
+/// would divide by zero.
+fn check_volume(
+    left: f64,
+    right: f64,
+    bottom: f64,
+    top: f64,
+    near: f64,
+    far: f64,
+) -> Result<(), LinalgError> {
+    if left == right || bottom == top || near == far {
+        return Err(LinalgError::invalid(
+            "Viewing volume must have a nonzero width, height and depth",
+        ));
+    }
+    Ok(())
+}
+
+// Projections follow the OpenGL conventions: the camera looks down the negative
+// z axis of a right-handed view space, and the visible volume is mapped onto
+// the cube [-1, 1]^3.
+impl Matrix<f64> {
+    /// Embeds a square matrix in the top left corner of a homogeneous matrix
+    /// one dimension larger.
+    pub fn homogeneous(&self) -> Result<Self, LinalgError> {
+        if !self.is_square() {
+            return Err(LinalgError::not_square("make homogeneous", self.shape()));
+        }
+        Ok(Matrix::id(self.nrows() + 1).embed_matrix(self, 0, 0))
+    }
+
+    /// The homogeneous matrix translating by `offset`.
+    pub fn translation(offset: &Vector<f64>) -> Self {
+        let dim = offset.dim();
+        let mut out = Matrix::id(dim + 1);
+        for i in 0..dim {
+            out[i][dim] = offset[i];
+        }
+        out
+    }
+
+    /// The homogeneous matrix scaling each axis by the matching factor.
+    pub fn scaling(factors: &Vector<f64>) -> Self {
+        let dim = factors.dim();
+        let mut out = Matrix::id(dim + 1);
+        for i in 0..dim {
+            out[i][i] = factors[i];
+        }
+        out
+    }
+
+    /// The homogeneous shear where, for example, `xy` is how much `y` is
+    /// added to `x`.
+    pub fn shear_3d(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
+        Matrix::from_rows(vec![
+            vec![1.0, xy, xz, 0.0],
+            vec![yx, 1.0, yz, 0.0],
+            vec![zx, zy, 1.0, 0.0],
+            vec![0.0, 0.0, 0.0, 1.0],
+        ])
+        .unwrap()
+    }
+
+    /// The perspective projection for a symmetric frustum with vertical field
+    /// of view `fov_y` (in radians) and width to height ratio `aspect`. The
+    /// field of view must lie strictly between 0 and π.
+    pub fn perspective(fov_y: f64, aspect: f64, near: f64, far: f64) -> Result<Self, LinalgError> {
+        if !(fov_y > 0.0 && fov_y < std::f64::consts::PI) {
+            return Err(LinalgError::invalid(
+                "Field of view must lie strictly between 0 and π",
+            ));
+        }
+        let top = near * (fov_y / 2.0).tan();
+        let right = top * aspect;
+        Matrix::frustum(-right, right, -top, top, near, far)
+    }
+
+    /// The perspective projection of the frustum whose near plane spans
+    /// `[left, right] × [bottom, top]` at distance `near`, which must be
+    /// positive.
+    pub fn frustum(
+        left: f64,
+        right: f64,
+        bottom: f64,
+        top: f64,
+        near: f64,
+        far: f64,
+    ) -> Result<Self, LinalgError> {
+        if near <= 0.0 {
+            return Err(LinalgError::invalid(
+                "Near plane of a perspective projection must be at a positive distance",
+            ));
+        }
+        check_volume(left, right, bottom, top, near, far)?;
+        let (width, height, depth) = (right - left, top - bottom, far - near);
+        Matrix::from_rows(vec![
+            vec![2.0 * near / width, 0.0, (right + left) / width, 0.0],
+            vec![0.0, 2.0 * near / height, (top + bottom) / height, 0.0],
+            vec![0.0, 0.0, -(far + near) / depth, -2.0 * far * near / depth],
+            vec![0.0, 0.0, -1.0, 0.0],
+        ])
+    }
+
+    /// The orthographic projection of the box `[left, right] × [bottom, top]`
+    /// between the planes at distance `near` and `far`.
+    pub fn orthographic(
+        left: f64,
+        right: f64,
+        bottom: f64,
+        top: f64,
+        near: f64,
+        far: f64,
+    ) -> Result<Self, LinalgError> {
+        check_volume(left, right, bottom, top, near, far)?;
+        let (width, height, depth) = (right - left, top - bottom, far - near);
+        Matrix::from_rows(vec![
+            vec![2.0 / width, 0.0, 0.0, -(right + left) / width],
+            vec![0.0, 2.0 / height, 0.0, -(top + bottom) / height],
+            vec![0.0, 0.0, -2.0 / depth, -(far + near) / depth],
+            vec![0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// The view matrix of a camera at `eye` looking towards `target`, with
+    /// `up` pointing roughly upwards on screen.
+    pub fn look_at(
+        eye: &Vector<f64>,
+        target: &Vector<f64>,
+        up: &Vector<f64>,
+    ) -> Result<Self, LinalgError> {
+        if eye.dim() != 3 || target.dim() != 3 || up.dim() != 3 {
+            return Err(LinalgError::invalid(
+                "Camera positions and directions must be 3 dimensional",
+            ));
+        }
+        let forward = target - eye;
+        if forward.norm() == 0.0 {
+            return Err(LinalgError::invalid(
+                "Camera cannot look at its own position",
+            ));
+        }
+        let forward = forward.normalised();
+        let side = forward.cross_product(up)?;
+        if side.norm() == 0.0 {
+            return Err(LinalgError::invalid(
+                "Up direction cannot be parallel to the viewing direction",
+            ));
+        }
+        let side = side.normalised();
+        let true_up = side.cross_product(&forward)?;
+
+        Matrix::from_rows(vec![
+            vec![side[0], side[1], side[2], -side.inner(eye)],
+            vec![true_up[0], true_up[1], true_up[2], -true_up.inner(eye)],
+            vec![-forward[0], -forward[1], -forward[2], forward.inner(eye)],
+            vec![0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Applies a 4×4 homogeneous transform to every row of `points` and
+    /// performs the perspective divide.
+    ///
+    /// Points may be given with three coordinates, in which case `w = 1` is
+    /// implied, or with four. Each output row is `x, y, z, w, visible`, where
+    /// `x, y, z` are divided by `w`, and `visible` is 1 if `w >= near` and 0
+    /// otherwise. `near` must be positive. Points behind the near plane have
+    /// zero coordinates, so only `w` and the flag carry information.
+    ///
+    /// With a `(width, height)` viewport, `x` and `y` are mapped from `[-1, 1]`
+    /// onto `[0, width]` and `[0, height]`, with `y` flipped to run downwards
+    /// like page coordinates in Typst: the top of the view is at `y = 0`.
+    pub fn transform_points(
+        &self,
+        points: &Matrix<f64>,
+        near: f64,
+        viewport: Option<(f64, f64)>,
+    ) -> Result<Matrix<f64>, LinalgError> {
+        let transform: SMatrix<f64, 4, 4> = self
+            .try_into()
+            .map_err(|_| LinalgError::invalid("Point transform must be a 4x4 matrix"))?;
+        if points.ncols() != 3 && points.ncols() != 4 {
+            return Err(LinalgError::invalid(
+                "Points must have three or four coordinates",
+            ));
+        }
+        if near <= 0.0 || near.is_nan() {
+            return Err(LinalgError::invalid(
+                "Near clipping distance must be positive",
+            ));
+        }
+
+        let mut out = Matrix::zero(points.nrows(), 5);
+        for (i, point) in points.rows().enumerate() {
+            let mut homogeneous = SVector { entries: [1.0; 4] };
+            homogeneous.entries[..point.len()].copy_from_slice(point);
+            let clip = transform * homogeneous;
+            let w = clip[3];
+            out[i][3] = w;
+            if w < near {
+                continue;
+            }
+
+            let (mut x, mut y) = (clip[0] / w, clip[1] / w);
+            if let Some((width, height)) = viewport {
+                x = (x + 1.0) / 2.0 * width;
+                y = (1.0 - y) / 2.0 * height;
+            }
+            out[i][0] = x;
+            out[i][1] = y;
+            out[i][2] = clip[2] / w;
+            out[i][4] = 1.0;
+        }
+        Ok(out)
+    }
+
+    /// The homogeneous 2D rotation by `angle` radians counterclockwise about
+    /// the point `(cx, cy)`.
+    pub fn rotation_2d_about(angle: f64, cx: f64, cy: f64) -> Self {
+        Matrix::about_point_2d(&Matrix::rotation_2d(angle), cx, cy)
+    }
+
+    /// The homogeneous 2D scaling by `sx` and `sy` that keeps `(cx, cy)` fixed.
+    pub fn scaling_2d_about(sx: f64, sy: f64, cx: f64, cy: f64) -> Self {
+        let linear = Matrix::from_rows(vec![vec![sx, 0.0], vec![0.0, sy]]).unwrap();
+        Matrix::about_point_2d(&linear, cx, cy)
+    }
+
+    /// The homogeneous 2D reflection across the line through `(px, py)` at
+    /// `angle` radians from the x axis.
+    pub fn reflection_2d(angle: f64, px: f64, py: f64) -> Self {
+        // Reflecting across a line at angle θ is rotating by 2θ after
+        // reflecting across the x axis.
+        let flip = Matrix::from_rows(vec![vec![1.0, 0.0], vec![0.0, -1.0]]).unwrap();
+        let linear = &Matrix::rotation_2d(2.0 * angle) * &flip;
+        Matrix::about_point_2d(&linear, px, py)
+    }
+
+    /// The homogeneous 2D shear adding `kx * y` to `x` and `ky * x` to `y`.
+    pub fn shear_2d(kx: f64, ky: f64) -> Self {
+        Matrix::from_rows(vec![
+            vec![1.0, kx, 0.0],
+            vec![ky, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+        ])
+        .unwrap()
+    }
+
+    /// Conjugates a 2×2 linear map by the translation to `(cx, cy)`, so that
+    /// it acts around that point instead of the origin.
+    fn about_point_2d(linear: &Matrix<f64>, cx: f64, cy: f64) -> Self {
+        let to_point = Matrix::translation(&Vector::from(vec![cx, cy]));
+        let from_point = Matrix::translation(&Vector::from(vec![-cx, -cy]));
+        let linear = Matrix::id(3).embed_matrix(linear, 0, 0);
+        &(&to_point * &linear) * &from_point
+    }
+
+    /// The single transform applying `transforms` one after another, starting
+    /// with the first. All must be square and of the same size.
+    pub fn compose(transforms: &[Matrix<f64>]) -> Result<Self, LinalgError> {
+        let first = transforms
+            .first()
+            .ok_or_else(|| LinalgError::invalid("At least one transform is needed"))?;
+        let mut out = Matrix::id(first.nrows());
+        for transform in transforms {
+            if !transform.is_square() || transform.nrows() != out.nrows() {
+                return Err(LinalgError::invalid(
+                    "Composed transforms must be square and of the same size",
+                ));
+            }
+            out = transform * &out;
+        }
+        Ok(out)
+    }
+
+    /// Applies a 2D transform to every row `x, y` of `points`. The transform is
+    /// either a 2×2 linear map or a 3×3 homogeneous one, in which case the
+    /// result is divided by `w`.
+    pub fn transform_points_2d(&self, points: &Matrix<f64>) -> Result<Matrix<f64>, LinalgError> {
+        let transform = match (self.nrows(), self.ncols()) {
+            (2, 2) => self.homogeneous()?,
+            (3, 3) => self.clone(),
+            _ => {
+                return Err(LinalgError::invalid(
+                    "2D transforms must be 2x2 or 3x3 matrices",
+                ))
+            }
+        };
+        if points.ncols() != 2 {
+            return Err(LinalgError::invalid("2D points must have two coordinates"));
+        }
+
+        let mut out = Matrix::zero(points.nrows(), 2);
+        for (i, point) in points.rows().enumerate() {
+            let image = transform.mul_vector(&Vector::from(vec![point[0], point[1], 1.0]))?;
+            if image[2] == 0.0 {
+                return Err(LinalgError::InvalidArgument(format!(
+                    "Point {} is mapped to infinity",
+                    i
+                )));
+            }
+            out[i][0] = image[0] / image[2];
+            out[i][1] = image[1] / image[2];
+        }
+        Ok(out)
+    }
+}
@@ -2,7 +2,11 @@ use num::complex::Complex64;
 use std::iter::zip;
 use std::vec;
 
-use crate::convert::Convertable;
+use crate::convert::{parse_entry, Convertable};
+use crate::error::{LinalgError, Position};
+use crate::format::{format_entries, NumberFormat};
+use crate::kernel::Kernel;
+use crate::tolerance::{frobenius_norm, Approximate, Tolerance};
 use crate::{common::*, Matrix};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -133,9 +137,7 @@ impl<T: Scalar> Vector<T> {
     }
 
     pub fn row_matrix(&self) -> Matrix<T> {
-        Matrix {
-            rows: vec![self.entries.clone()],
-        }
+        Matrix::from_vec(1, self.dim(), self.entries.clone()).unwrap()
     }
 
     pub fn column_matrix(&self) -> Matrix<T> {
@@ -148,7 +150,7 @@ impl<T: Scalar> Vector<T> {
         self_mat * other_mat
     }
 
-    pub fn cross_product(&self, rhs: &Self) -> Result<Self, String> {
+    pub fn cross_product(&self, rhs: &Self) -> Result<Self, LinalgError> {
         match (self.dim(), rhs.dim()) {
             (0, 0) => Ok(Vector::zero(0)),
             (1, 1) => Ok(Vector::zero(1)),
@@ -170,15 +172,23 @@ impl<T: Scalar> Vector<T> {
                 }
                 prod
             })),
-            _ => Err(
-                "Cross product is only defined for pairs of 0, 1, 3 or 7 dimensional vectors"
-                    .to_owned(),
-            ),
+            _ => Err(LinalgError::mismatch(
+                "take the cross product of",
+                (self.dim(), 1),
+                (rhs.dim(), 1),
+            )),
         }
     }
 
     pub fn is_ortogonal_to(&self, other: &Self) -> bool {
-        self.inner(other).is_zero()
+        self.is_ortogonal_to_within(other, Tolerance::EXACT)
+    }
+
+    /// Whether the inner product is within `tol` of zero, relative to the
+    /// product of the norms.
+    pub fn is_ortogonal_to_within(&self, other: &Self, tol: Tolerance) -> bool {
+        let scale = frobenius_norm(&self.entries) * frobenius_norm(&other.entries);
+        self.inner(other).is_negligible(tol.threshold(scale))
     }
 }
 
@@ -188,9 +198,14 @@ pub trait InnerProduct<T: Scalar> {
 
 impl<T: Scalar> InnerProduct<T> for Vector<T> {
     default fn inner(&self, other: &Self) -> T {
-        zip(self.entries.iter(), other.entries.iter())
-            .map(|(a, b)| *a * *b)
-            .sum::<T>()
+        T::dot(&self.entries, &other.entries)
+    }
+}
+
+/// The complex inner product is conjugate linear in its second argument.
+impl InnerProduct<Complex64> for Vector<Complex64> {
+    fn inner(&self, other: &Self) -> Complex64 {
+        Vector::<Complex64>::inner(self, other)
     }
 }
 
@@ -203,9 +218,11 @@ impl Vector<f64> {
         self.scale(1.0 / self.norm())
     }
 
-    pub fn angle_with(&self, other: &Self) -> Result<f64, String> {
+    pub fn angle_with(&self, other: &Self) -> Result<f64, LinalgError> {
         if self.is_zero() || other.is_zero() {
-            return Err("Angles are not defined for the zero vector".to_owned());
+            return Err(LinalgError::invalid(
+                "Angles are not defined for the zero vector",
+            ));
         }
         Ok((self.inner(other) / self.norm() / other.norm()).acos())
     }
@@ -238,30 +255,22 @@ impl Vector<Complex64> {
 }
 
 impl<T: Scalar> Convertable for Vector<T> {
-    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
-        let s = std::str::from_utf8(bytes).unwrap().to_owned();
+    fn from_bytes(bytes: &[u8]) -> Result<Self, LinalgError> {
+        let s = std::str::from_utf8(bytes)?;
 
         let mut entries: Vec<T> = Vec::new();
-        for entry in s.split(',') {
-            let res = T::from_str(entry);
-            match res {
-                Ok(num) => entries.push(num),
-                Err(_) => return Err("Unable to parse number in vector".to_owned()),
-            }
+        for (i, entry) in s.split(',').enumerate() {
+            entries.push(parse_entry(entry, Position::Entry(i))?);
         }
         Ok(Vector { entries })
     }
 
     fn to_bytes(&self) -> Vec<u8> {
-        let s = self
-            .entries
-            .iter()
-            .map(|entry| {
-                let entry_s = entry.to_string();
-                truncate_zeroes(entry_s)
-            })
-            .collect::<Vec<String>>()
-            .join(",");
+        self.to_bytes_with(&NumberFormat::ROUND_TRIP)
+    }
+
+    fn to_bytes_with(&self, format: &NumberFormat) -> Vec<u8> {
+        let s = format_entries(&self.entries, format).join(",");
         s.as_bytes().to_vec()
     }
 }
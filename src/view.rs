@@ -0,0 +1,272 @@
+use std::ops::Range;
+
+use crate::common::*;
+use crate::error::LinalgError;
+use crate::matrix::Matrix;
+use crate::vector::Vector;
+
+/// A borrowed rectangular block of a matrix.
+///
+/// Entry `(i, j)` of the view lives at `data[i * stride + j]`, so every row of
+/// the view is a contiguous slice of the underlying row-major buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct MatrixView<'a, T>
+where
+    T: Scalar,
+{
+    data: &'a [T],
+    stride: usize,
+    nrows: usize,
+    ncols: usize,
+}
+
+/// A mutably borrowed rectangular block of a matrix.
+#[derive(Debug)]
+pub struct MatrixViewMut<'a, T>
+where
+    T: Scalar,
+{
+    data: &'a mut [T],
+    stride: usize,
+    nrows: usize,
+    ncols: usize,
+}
+
+/// Checks a block against the shape of its host and returns the offset and
+/// length of the part of the host buffer that the block spans.
+fn block_span(
+    (nrows, ncols, stride): (usize, usize, usize),
+    rows: &Range<usize>,
+    cols: &Range<usize>,
+) -> Result<(usize, usize), LinalgError> {
+    if rows.start > rows.end || rows.end > nrows {
+        return Err(LinalgError::InvalidArgument(format!(
+            "Row range {}..{} does not fit in {} rows",
+            rows.start, rows.end, nrows
+        )));
+    }
+    if cols.start > cols.end || cols.end > ncols {
+        return Err(LinalgError::InvalidArgument(format!(
+            "Column range {}..{} does not fit in {} columns",
+            cols.start, cols.end, ncols
+        )));
+    }
+    if rows.is_empty() || cols.is_empty() {
+        return Ok((0, 0));
+    }
+    let offset = rows.start * stride + cols.start;
+    let len = (rows.len() - 1) * stride + cols.len();
+    Ok((offset, len))
+}
+
+impl<T: Scalar> Matrix<T> {
+    pub fn as_view(&self) -> MatrixView<'_, T> {
+        MatrixView {
+            data: self.as_slice(),
+            stride: self.ncols(),
+            nrows: self.nrows(),
+            ncols: self.ncols(),
+        }
+    }
+
+    pub fn as_view_mut(&mut self) -> MatrixViewMut<'_, T> {
+        let (nrows, ncols) = (self.nrows(), self.ncols());
+        MatrixViewMut {
+            data: self.as_mut_slice(),
+            stride: ncols,
+            nrows,
+            ncols,
+        }
+    }
+
+    pub fn view(
+        &self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Result<MatrixView<'_, T>, LinalgError> {
+        self.as_view().view(rows, cols)
+    }
+
+    pub fn view_mut(
+        &mut self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Result<MatrixViewMut<'_, T>, LinalgError> {
+        self.as_view_mut().into_view_mut(rows, cols)
+    }
+
+    pub fn row(&self, row: usize) -> Result<MatrixView<'_, T>, LinalgError> {
+        self.view(row..row + 1, 0..self.ncols())
+    }
+
+    pub fn column(&self, col: usize) -> Result<MatrixView<'_, T>, LinalgError> {
+        self.view(0..self.nrows(), col..col + 1)
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> Result<MatrixViewMut<'_, T>, LinalgError> {
+        let cols = self.ncols();
+        self.view_mut(row..row + 1, 0..cols)
+    }
+
+    pub fn column_mut(&mut self, col: usize) -> Result<MatrixViewMut<'_, T>, LinalgError> {
+        let rows = self.nrows();
+        self.view_mut(0..rows, col..col + 1)
+    }
+}
+
+impl<'a, T: Scalar> MatrixView<'a, T> {
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    pub fn row_slice(&self, row: usize) -> &'a [T] {
+        assert!(row < self.nrows, "Row index exceeds last row");
+        if self.ncols == 0 {
+            return &[];
+        }
+        &self.data[row * self.stride..row * self.stride + self.ncols]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> + '_ {
+        (0..self.nrows).map(move |i| self.row_slice(i))
+    }
+
+    pub fn view(
+        &self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Result<MatrixView<'a, T>, LinalgError> {
+        let (offset, len) = block_span((self.nrows, self.ncols, self.stride), &rows, &cols)?;
+        Ok(MatrixView {
+            data: &self.data[offset..offset + len],
+            stride: self.stride,
+            nrows: rows.len(),
+            ncols: cols.len(),
+        })
+    }
+
+    pub fn column(&self, col: usize) -> Result<MatrixView<'a, T>, LinalgError> {
+        self.view(0..self.nrows, col..col + 1)
+    }
+
+    pub fn to_matrix(self) -> Matrix<T> {
+        let data = self.rows().flat_map(|row| row.iter().copied()).collect();
+        Matrix::from_vec(self.nrows, self.ncols, data).unwrap()
+    }
+
+    /// Copies the entries of the view into a vector in row-major order, which
+    /// for a single row or column is the row or column itself.
+    pub fn to_vector(self) -> Vector<T> {
+        Vector::from(
+            self.rows()
+                .flat_map(|row| row.iter().copied())
+                .collect::<Vec<T>>(),
+        )
+    }
+}
+
+impl<T: Scalar> std::ops::Index<(usize, usize)> for MatrixView<'_, T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        assert!(col < self.ncols, "Column index exceeds last column");
+        &self.row_slice(row)[col]
+    }
+}
+
+impl<'a, T: Scalar> MatrixViewMut<'a, T> {
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    pub fn as_view(&self) -> MatrixView<'_, T> {
+        MatrixView {
+            data: self.data,
+            stride: self.stride,
+            nrows: self.nrows,
+            ncols: self.ncols,
+        }
+    }
+
+    pub fn row_slice_mut(&mut self, row: usize) -> &mut [T] {
+        assert!(row < self.nrows, "Row index exceeds last row");
+        if self.ncols == 0 {
+            return &mut [];
+        }
+        &mut self.data[row * self.stride..row * self.stride + self.ncols]
+    }
+
+    pub fn view_mut(
+        &mut self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Result<MatrixViewMut<'_, T>, LinalgError> {
+        let reborrowed = MatrixViewMut {
+            data: &mut *self.data,
+            stride: self.stride,
+            nrows: self.nrows,
+            ncols: self.ncols,
+        };
+        reborrowed.into_view_mut(rows, cols)
+    }
+
+    fn into_view_mut(
+        self,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Result<MatrixViewMut<'a, T>, LinalgError> {
+        let (offset, len) = block_span((self.nrows, self.ncols, self.stride), &rows, &cols)?;
+        Ok(MatrixViewMut {
+            data: &mut self.data[offset..offset + len],
+            stride: self.stride,
+            nrows: rows.len(),
+            ncols: cols.len(),
+        })
+    }
+
+    pub fn fill(&mut self, value: T) {
+        for i in 0..self.nrows {
+            self.row_slice_mut(i).fill(value);
+        }
+    }
+
+    pub fn copy_from(&mut self, other: &MatrixView<'_, T>) -> Result<(), LinalgError> {
+        if self.nrows != other.nrows() || self.ncols != other.ncols() {
+            return Err(LinalgError::mismatch(
+                "copy between blocks of",
+                (self.nrows, self.ncols),
+                (other.nrows(), other.ncols()),
+            ));
+        }
+        for (i, row) in other.rows().enumerate() {
+            self.row_slice_mut(i).copy_from_slice(row);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Scalar> std::ops::Index<(usize, usize)> for MatrixViewMut<'_, T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        assert!(row < self.nrows, "Row index exceeds last row");
+        assert!(col < self.ncols, "Column index exceeds last column");
+        &self.data[row * self.stride + col]
+    }
+}
+
+impl<T: Scalar> std::ops::IndexMut<(usize, usize)> for MatrixViewMut<'_, T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        assert!(row < self.nrows, "Row index exceeds last row");
+        assert!(col < self.ncols, "Column index exceeds last column");
+        &mut self.data[row * self.stride + col]
+    }
+}